@@ -1,14 +1,12 @@
-use crate::{
-    components::icons::{GearIcon, PauseIcon, PlayIcon, RefreshIcon, TrashIcon},
-    universe::Universe,
-};
+use crate::components::icons::{DotIcon, GearIcon, PauseIcon, PlayIcon, RefreshIcon, TrashIcon};
+use crate::engine::ENGINE_KINDS;
 use gloo_events::{EventListener, EventListenerOptions};
 use gloo_timers::callback::Interval;
 use wasm_bindgen::JsCast;
-use web_sys::Node;
+use web_sys::{HtmlSelectElement, Node};
 use yew::prelude::*;
 
-use super::universe_ctx::Command;
+use super::universe_ctx::{Command, MessageContext};
 
 #[derive(Debug)]
 enum GearState {
@@ -61,7 +59,7 @@ pub fn action_button(props: &ActionButtonProps) -> Html {
 
 #[function_component(Actions)]
 pub fn actions() -> Html {
-    let universe = use_context::<UseReducerHandle<Universe>>().expect("no universe ctx found");
+    let universe = use_context::<MessageContext>().expect("no universe ctx found");
     let gear_state = use_state(|| GearState::Collapsed);
     let cloned_gear_state = gear_state.clone();
     let on_gear_click = Callback::from(move |_| match *cloned_gear_state {
@@ -70,7 +68,9 @@ pub fn actions() -> Html {
     });
     let clear_btn_ref = use_node_ref();
     let reset_btn_ref = use_node_ref();
+    let seed_btn_ref = use_node_ref();
     let gear_btn_ref = use_node_ref();
+    let engine_select_ref = use_node_ref();
     let interval: UseStateHandle<Option<Interval>> = use_state(|| None);
     let interval_clone = interval.clone();
     let universe_clone = universe.clone();
@@ -95,22 +95,62 @@ pub fn actions() -> Html {
         interval_clone.set(None);
         play_state_clone.set(PlayState::Paused);
     });
+    let interval_clone = interval.clone();
+    let universe_clone = universe.clone();
+    let on_seed_click = Callback::from(move |_| {
+        interval_clone.set(None);
+        universe_clone.dispatch(Command::Seed);
+    });
+    // only the engines running the current rule are offered
+    let engines = ENGINE_KINDS
+        .iter()
+        .filter(|kind| kind.supports(universe.engine().rule()))
+        .map(|kind| {
+            html! {
+                <option value={kind.to_string()} selected={*kind == universe.kind}>
+                    {kind.to_string()}
+                </option>
+            }
+        })
+        .collect::<Html>();
+    let universe_clone = universe.clone();
+    let on_engine_change = Callback::from(move |e: Event| {
+        let value = e.target_unchecked_into::<HtmlSelectElement>().value();
+        match value.parse() {
+            Ok(kind) => universe_clone.dispatch(Command::SetEngine(kind)),
+            Err(error) => log::warn!("{}", error),
+        }
+    });
     let on_shuffle_click = Callback::from(move |_| {
         interval.set(None);
-        universe.dispatch(Command::Shuffle);
+        universe.dispatch(Command::Shuffle(None));
     });
     let options = EventListenerOptions::run_in_capture_phase();
     let btns = vec![
         clear_btn_ref.clone(),
         reset_btn_ref.clone(),
+        seed_btn_ref.clone(),
         gear_btn_ref.clone(),
+        engine_select_ref.clone(),
     ];
-    let (gear_trash_actions_cls, gear_reset_actions_cls) = match *gear_state {
+    let (
+        gear_engine_actions_cls,
+        gear_seed_actions_cls,
+        gear_trash_actions_cls,
+        gear_reset_actions_cls,
+    ) = match *gear_state {
         GearState::Expanded => (
+            "-translate-y-[180px] opacity-100",
+            "-translate-y-[140px] opacity-100",
             "-translate-y-[90px] opacity-100",
             "-translate-y-[40px] opacity-100",
         ),
-        GearState::Collapsed => ("translate-y-0 opacity-0", "translate-y-0 opacity-0"),
+        GearState::Collapsed => (
+            "translate-y-0 opacity-0 pointer-events-none",
+            "translate-y-0 opacity-0",
+            "translate-y-0 opacity-0",
+            "translate-y-0 opacity-0",
+        ),
     };
     let _ = use_state(move || {
         EventListener::new_with_options(
@@ -137,6 +177,12 @@ pub fn actions() -> Html {
         <div class="flex fixed bottom-0 right-0 p-2">
             <div class="space-y-2">
                 <div class="relative">
+                    <select ref={engine_select_ref} onchange={on_engine_change} class={format!("absolute right-0 bg-gray-900 text-gray-300 rounded px-1 text-sm transition-all {}", gear_engine_actions_cls)}>
+                        {engines}
+                    </select>
+                    <ActionButton reference={seed_btn_ref} onclick={on_seed_click} class={format!("grid place-items-center absolute transition-all {}", gear_seed_actions_cls)}>
+                        <DotIcon class="h-10 w-10 fill-gray-400"/>
+                    </ActionButton>
                     <ActionButton reference={clear_btn_ref} onclick={on_clear_click} class={format!("grid place-items-center absolute transition-all {}", gear_trash_actions_cls)}>
                         <TrashIcon class="h-10 w-10 fill-gray-400"/>
                     </ActionButton>
@@ -1,5 +1,10 @@
 use crate::components::actions::Actions;
+use crate::components::census::CensusTable;
 use crate::components::existence::Existence;
+use crate::components::graph::Graph;
+use crate::components::hud::Hud;
+use crate::components::jump::Jump;
+use crate::components::soup::SoupSettings;
 use crate::components::universe_ctx::UniverseProvider;
 
 use yew::prelude::*;
@@ -10,7 +15,12 @@ pub fn app() -> Html {
         <div class="h-screen bg-black">
             <UniverseProvider>
                 <Existence />
+                <Hud />
+                <Graph />
+                <CensusTable />
                 <Actions />
+                <Jump />
+                <SoupSettings />
             </UniverseProvider>
         </div>
     }
@@ -2,19 +2,57 @@ use std::rc::Rc;
 
 use yew::prelude::*;
 
-use crate::universe::{Entity, Universe, CELL_SIZE};
+use crate::universe::rule::Rule;
+use crate::universe::{Entity, CELL_SIZE};
 
-use super::universe_ctx::Command;
+use super::universe_ctx::{Command, MessageContext};
 
 #[derive(PartialEq, Properties, Clone)]
 pub struct Props {
     pub value: Rc<Entity>,
 }
 
+/// The CSS color of a cell (`None` for the default one) and the opacity of its glow.
+pub fn cell_style(entity: &Entity, rule: &Rule) -> (Option<String>, f64) {
+    let state = entity.state;
+    let color = rule.color(state).or_else(|| rule.team_color(entity.team));
+    if let Some((red, green, blue)) = color {
+        return (Some(format!("rgb({}, {}, {})", red, green, blue)), 1.0);
+    }
+    if state <= 1 {
+        return (None, 1.0);
+    }
+    let age = f64::from(state - 1) / f64::from(rule.states().max(2) - 1);
+    (
+        Some(format!(
+            "hsl({:.0}, 80%, {:.0}%)",
+            30.0 * (1.0 - age),
+            50.0 - 30.0 * age
+        )),
+        1.0 - age,
+    )
+}
+
 #[function_component(Bean)]
 pub fn bean(props: &Props) -> Html {
-    let universe = use_context::<UseReducerHandle<Universe>>().expect("no universe ctx found");
-    let (x, y) = (props.value.line * CELL_SIZE, props.value.column * CELL_SIZE);
+    let universe = use_context::<MessageContext>().expect("no universe ctx found");
+    let (fill, opacity) = cell_style(&props.value, universe.engine().rule());
+    let color = if fill.is_none() { "bg-cyan-800" } else { "" };
+    let fill = fill.map_or_else(String::new, |fill| format!("background-color: {};", fill));
+    let glow = if opacity < 1.0 {
+        format!("opacity: {:.2};", opacity)
+    } else {
+        String::new()
+    };
+    let shift = universe
+        .engine()
+        .rule()
+        .neighborhood()
+        .shift(props.value.line);
+    let (x, y) = (
+        props.value.line * CELL_SIZE,
+        props.value.column * CELL_SIZE + shift,
+    );
     let style = format!(
         "height: {}px; width: {}px; top: {}px; left: {}px",
         CELL_SIZE, CELL_SIZE, x, y
@@ -26,8 +64,8 @@ pub fn bean(props: &Props) -> Html {
     html! {
         <div class="absolute" {style}>
             <div class="relative" style={format!("height: {}px; width: {}px;", CELL_SIZE, CELL_SIZE)}>
-                <button {onclick} class="absolute w-full h-full border border-black bg-cyan-800 rounded-full" style={format!("height: {}px; width: {}px;", CELL_SIZE, CELL_SIZE)}/>
-                <div class="transition-all bg-yellow-400 hover:bg-yellow-500 rounded-full blur-lg" style={format!("height: {}px; width: {}px;", CELL_SIZE, CELL_SIZE)}>
+                <button {onclick} class={classes!("absolute", "w-full", "h-full", "border", "border-black", color, "rounded-full")} style={format!("height: {}px; width: {}px; {}", CELL_SIZE, CELL_SIZE, fill)}/>
+                <div class="transition-all bg-yellow-400 hover:bg-yellow-500 rounded-full blur-lg" style={format!("height: {}px; width: {}px; {}", CELL_SIZE, CELL_SIZE, glow)}>
                 </div>
             </div>
         </div>
@@ -0,0 +1,100 @@
+use super::bean::cell_style;
+use super::universe_ctx::MessageContext;
+use super::Dimensions;
+use crate::universe::rule::Rule;
+use crate::universe::{Entity, CELL_SIZE};
+use std::collections::BTreeMap;
+use std::f64::consts::TAU;
+use std::rc::Rc;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use yew::prelude::*;
+
+/// Fill of the live cells without a color, the `bg-cyan-800` of the beans.
+const DEFAULT_COLOR: &str = "rgb(21, 94, 117)";
+/// Glow behind the cells, the `bg-yellow-400` of the beans.
+const GLOW_COLOR: &str = "rgb(250, 204, 21)";
+/// Blur of the glow in pixels, as `blur-lg`.
+const GLOW_BLUR: f64 = 16.0;
+
+#[derive(PartialEq, Properties, Clone)]
+pub struct Props {
+    pub cells: Rc<Vec<Entity>>,
+    pub dimensions: Dimensions,
+    /// Draws the blurred glow of the beans behind the cells.
+    pub glow: bool,
+}
+
+fn trace(context: &CanvasRenderingContext2d, rule: &Rule, cells: &[&Entity]) {
+    let radius = f64::from(CELL_SIZE) / 2.0;
+    for entity in cells {
+        let x = f64::from(entity.column * CELL_SIZE + rule.neighborhood().shift(entity.line));
+        let y = f64::from(entity.line * CELL_SIZE);
+        context.move_to(x + 2.0 * radius, y + radius);
+        let _ = context.arc(x + radius, y + radius, radius, 0.0, TAU);
+    }
+}
+
+fn draw(context: &CanvasRenderingContext2d, props: &Props, rule: &Rule) {
+    let Dimensions { width, height } = props.dimensions;
+    context.clear_rect(0.0, 0.0, f64::from(width), f64::from(height));
+    // the cells of the same style are filled at once, which is much faster than one by one
+    let mut styles: BTreeMap<(String, u8), Vec<&Entity>> = BTreeMap::new();
+    for entity in props.cells.iter() {
+        let (color, opacity) = cell_style(entity, rule);
+        let color = color.unwrap_or_else(|| DEFAULT_COLOR.to_string());
+        let opacity = (opacity * 100.0).round() as u8;
+        styles.entry((color, opacity)).or_default().push(entity);
+    }
+    if props.glow {
+        context.set_fill_style(&JsValue::from_str(GLOW_COLOR));
+        context.set_shadow_color(GLOW_COLOR);
+        context.set_shadow_blur(GLOW_BLUR);
+        for ((_, opacity), cells) in &styles {
+            context.set_global_alpha(f64::from(*opacity) / 100.0);
+            context.begin_path();
+            trace(context, rule, cells);
+            context.fill();
+        }
+        context.set_shadow_blur(0.0);
+        context.set_global_alpha(1.0);
+    }
+    context.set_stroke_style(&JsValue::from_str("black"));
+    context.set_line_width(1.0);
+    for ((color, _), cells) in &styles {
+        context.set_fill_style(&JsValue::from_str(color));
+        context.begin_path();
+        trace(context, rule, cells);
+        context.fill();
+        context.stroke();
+    }
+}
+
+/// Draws the cells on a canvas, for populations too large for a [`Bean`](super::bean::Bean)
+/// per cell. The canvas lets the clicks through to the universe below.
+#[function_component(CellCanvas)]
+pub fn cell_canvas(props: &Props) -> Html {
+    let universe = use_context::<MessageContext>().expect("no universe ctx found");
+    let canvas = use_node_ref();
+    let canvas_clone = canvas.clone();
+    let props_clone = props.clone();
+    // drawn again after each render, once the canvas has its new size
+    use_effect(move || {
+        let context = canvas_clone
+            .cast::<HtmlCanvasElement>()
+            .and_then(|canvas| canvas.get_context("2d").ok().flatten())
+            .and_then(|context| context.dyn_into::<CanvasRenderingContext2d>().ok());
+        match context {
+            Some(context) => draw(&context, &props_clone, universe.engine().rule()),
+            None => log::warn!("Unable to draw on the canvas"),
+        }
+    });
+    html! {
+        <canvas
+            ref={canvas}
+            width={props.dimensions.width.to_string()}
+            height={props.dimensions.height.to_string()}
+            class="absolute top-0 left-0 pointer-events-none"
+        />
+    }
+}
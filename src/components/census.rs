@@ -0,0 +1,63 @@
+use super::universe_ctx::MessageContext;
+use crate::universe::apgcode;
+use crate::universe::census::Census;
+use yew::prelude::*;
+
+/// Table of the objects of the universe by apgcode, taken on demand once a soup has settled.
+#[function_component(CensusTable)]
+pub fn census_table() -> Html {
+    let universe = use_context::<MessageContext>().expect("no universe ctx found");
+    // the census and the generation it was taken at, or None when the rule is not supported
+    let census: UseStateHandle<Option<(u64, Option<Census>)>> = use_state(|| None);
+    let census_clone = census.clone();
+    let on_census_click = Callback::from(move |_| {
+        let generation = universe.engine().generation();
+        census_clone.set(Some((
+            generation,
+            Census::take(&universe.engine().to_universe()),
+        )));
+    });
+    let census_clone = census.clone();
+    let on_close_click = Callback::from(move |_| census_clone.set(None));
+    let button = "bg-gray-900 text-gray-300 rounded px-2 hover:bg-gray-700";
+    let table = match &*census {
+        None => html! {},
+        Some((_, None)) => html! {
+            <div class="text-gray-400">{"Objects of this rule have no apgcode"}</div>
+        },
+        Some((generation, Some(census))) => {
+            let rows = census
+                .objects()
+                .into_iter()
+                .map(|(code, count)| {
+                    html! {
+                        <tr>
+                            <td class="pr-4 font-mono">{code}</td>
+                            <td class="pr-4 text-gray-400">{apgcode::name(code).unwrap_or_default()}</td>
+                            <td class="text-right">{count}</td>
+                        </tr>
+                    }
+                })
+                .collect::<Html>();
+            html! {
+                <div class="max-h-64 overflow-y-auto bg-gray-900/75 rounded p-2">
+                    <div class="text-gray-400">
+                        {format!("{} objects at generation {}", census.total(), generation)}
+                    </div>
+                    <table>{rows}</table>
+                </div>
+            }
+        }
+    };
+    html! {
+        <div class="fixed bottom-10 left-0 p-2 space-y-1 text-sm text-gray-300">
+            {table}
+            <div class="flex space-x-2">
+                <button onclick={on_census_click} class={button}>{"census"}</button>
+                if census.is_some() {
+                    <button onclick={on_close_click} class={button}>{"close"}</button>
+                }
+            </div>
+        </div>
+    }
+}
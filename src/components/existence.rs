@@ -1,13 +1,39 @@
-use super::universe_ctx::Command;
+use super::universe_ctx::{load_command, Command, MessageContext};
+use super::Dimensions;
 use crate::components::bean::Bean;
+use crate::components::canvas::CellCanvas;
 use crate::components::universe_ctx::window_dimensions;
-use crate::universe::Universe;
+use crate::universe::neighborhood::Neighborhood;
+use crate::universe::rule::Rule;
+use crate::universe::table::RuleTable;
+use crate::universe::{Boundaries, CELL_SIZE};
 use gloo_events::EventListener;
+use gloo_file::callbacks::{read_as_text, FileReader};
+use gloo_file::File;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use web_sys::{ClipboardEvent, HtmlInputElement};
 use yew::prelude::*;
 
+/// Population above which the cells are drawn on a canvas rather than as beans, whose DOM
+/// becomes too slow to update.
+pub const CANVAS_THRESHOLD: usize = 1000;
+
+fn visible_area(dimensions: &Dimensions, neighborhood: &Neighborhood) -> Boundaries {
+    let line_max = dimensions.height / CELL_SIZE;
+    // hexagonal lines are shifted to the left, showing columns further to the right
+    let shift = -neighborhood.shift(line_max) / CELL_SIZE;
+    Boundaries {
+        line_min: 0,
+        line_max,
+        column_min: 0,
+        column_max: dimensions.width / CELL_SIZE + shift,
+    }
+}
+
 #[function_component(Existence)]
 pub fn existence() -> Html {
-    let universe = use_context::<UseReducerHandle<Universe>>().expect("no universe ctx found");
+    let universe = use_context::<MessageContext>().expect("no universe ctx found");
     let dimensions = use_state(window_dimensions);
     let dimensions_clone = dimensions.clone();
     let _ = use_state(|| {
@@ -16,24 +42,161 @@ pub fn existence() -> Html {
             dimensions_clone.set(new_dyn);
         })
     });
-    let entities = universe
-        .entities
-        .iter()
-        .filter(|e| (*e).is_visible(&dimensions))
-        .map(|e| {
+    let universe_clone = universe.clone();
+    let _ = use_state(|| {
+        EventListener::new(&gloo_utils::window(), "paste", move |e| {
+            // pasting in a field is left to the field
+            let target = e.target();
+            if target.is_some_and(|target| target.dyn_ref::<HtmlInputElement>().is_some()) {
+                return;
+            }
+            let content = e
+                .dyn_ref::<ClipboardEvent>()
+                .and_then(|e| e.clipboard_data())
+                .and_then(|data| data.get_data("text").ok());
+            if let Some(content) = content {
+                match load_command(&content) {
+                    Ok(command) => universe_clone.dispatch(command),
+                    Err(error) => log::warn!("Unable to paste pattern: {}", error),
+                }
+            }
+        })
+    });
+    // None follows the population, otherwise the renderer chosen by the user
+    let canvas: UseStateHandle<Option<bool>> = use_state(|| None);
+    let glow = use_state(|| true);
+    let use_canvas = canvas.unwrap_or(universe.engine().population() > CANVAS_THRESHOLD);
+    let cells = universe.engine().cells_in_rect(&visible_area(
+        &dimensions,
+        universe.engine().rule().neighborhood(),
+    ));
+    let entities = if use_canvas {
+        html! {
+            <CellCanvas cells={Rc::new(cells)} dimensions={(*dimensions).clone()} glow={*glow} />
+        }
+    } else {
+        cells
+            .into_iter()
+            .map(|e| {
+                let key = format!("c{}-l{}-s{}-t{}", e.column, e.line, e.state, e.team);
+                html! {
+                    <Bean {key} value={Rc::new(e)} />
+                }
+            })
+            .collect::<Html>()
+    };
+    let button = "bg-gray-900 text-gray-300 rounded px-2 hover:bg-gray-700";
+    let canvas_clone = canvas.clone();
+    let on_canvas_click = Callback::from(move |_| canvas_clone.set(Some(!use_canvas)));
+    let glow_clone = glow.clone();
+    let on_glow_click = Callback::from(move |_| glow_clone.set(!*glow_clone));
+    let grid = match universe.engine().rule().topology().size() {
+        Some((width, height)) => {
+            let skew = match universe.engine().rule().neighborhood() {
+                // follows the hexagonal lines, shifted by half a cell each
+                Neighborhood::Hexagonal => {
+                    "transform-origin: top left; transform: skewX(-26.565deg);"
+                }
+                _ => "",
+            };
+            let style = format!(
+                "height: {}px; width: {}px; {}",
+                height * CELL_SIZE,
+                width * CELL_SIZE,
+                skew
+            );
             html! {
-                <Bean key={format!("c{}-l{}", e.column, e.line)} value={e.clone()} />
+                <div class="absolute top-0 left-0 border border-gray-700 pointer-events-none" {style}/>
             }
+        }
+        None => html! {},
+    };
+    let reader: UseStateHandle<Option<FileReader>> = use_state(|| None);
+    let universe_clone = universe.clone();
+    let ondrop = Callback::from(move |e: DragEvent| {
+        e.prevent_default();
+        let file = e
+            .data_transfer()
+            .and_then(|data| data.files())
+            .and_then(|files| files.get(0));
+        if let Some(file) = file {
+            let universe = universe_clone.clone();
+            reader.set(Some(read_as_text(
+                &File::from(file),
+                move |content| match content {
+                    Ok(content) if content.trim_start().starts_with("@RULE") => {
+                        match RuleTable::parse(&content) {
+                            Ok(table) => match Rule::parse(table.register().name()) {
+                                Ok(rule) => universe.dispatch(Command::SetRule(rule)),
+                                Err(error) => log::warn!("Unable to use rule: {}", error),
+                            },
+                            Err(error) => log::warn!("Unable to load rule: {}", error),
+                        }
+                    }
+                    Ok(content) => match load_command(&content) {
+                        Ok(command) => universe.dispatch(command),
+                        Err(error) => log::warn!("Unable to load pattern: {}", error),
+                    },
+                    Err(error) => log::warn!("Unable to read file: {}", error),
+                },
+            )));
+        }
+    });
+    let ondragover = Callback::from(|e: DragEvent| e.prevent_default());
+    // the color painted by clicks, for rules with colored cells
+    let team = use_state(|| 0);
+    let palette = (0..universe.engine().rule().teams())
+        .filter_map(|value| {
+            let (red, green, blue) = universe.engine().rule().team_color(value)?;
+            let ring = if value == *team {
+                "ring-2 ring-white"
+            } else {
+                ""
+            };
+            let style = format!("background-color: rgb({}, {}, {});", red, green, blue);
+            let team = team.clone();
+            let onclick = Callback::from(move |_| team.set(value));
+            Some(html! {
+                <button {onclick} {style} class={classes!("w-6", "h-6", "rounded-full", ring)}/>
+            })
         })
         .collect::<Html>();
+    let selected = *team;
     let onclick = Callback::from(move |e: MouseEvent| {
         let x = e.x();
         let y = e.y();
-        universe.dispatch(Command::Add { x, y });
+        // the beans delete themselves, the cells of the canvas are toggled here
+        if use_canvas {
+            universe.dispatch(Command::Toggle {
+                x,
+                y,
+                team: selected,
+            });
+        } else {
+            universe.dispatch(Command::Add {
+                x,
+                y,
+                team: selected,
+            });
+        }
     });
     html! {
-        <div {onclick} class="h-full w-full overflow-hidden relative">
-          {entities}
-        </div>
+        <>
+            <div {onclick} {ondrop} {ondragover} class="h-full w-full overflow-hidden relative">
+              {grid}
+              {entities}
+            </div>
+            <div class="flex fixed top-0 left-0 p-2 space-x-2 text-sm">
+              {palette}
+              <button onclick={on_canvas_click} class={button}>
+                {if use_canvas { "beans" } else { "canvas" }}
+              </button>
+              if use_canvas {
+                <button onclick={on_glow_click} class={button}>
+                  {if *glow { "no glow" } else { "glow" }}
+                </button>
+              }
+            </div>
+        </>
     }
 }
@@ -0,0 +1,108 @@
+use super::universe_ctx::MessageContext;
+use crate::statistics::{Record, HISTORY_SIZE};
+use yew::prelude::*;
+
+/// Generations shown by the zoom levels of the time axis, up to the whole history.
+const ZOOMS: [u64; 4] = [
+    HISTORY_SIZE as u64 / 64,
+    HISTORY_SIZE as u64 / 16,
+    HISTORY_SIZE as u64 / 4,
+    HISTORY_SIZE as u64,
+];
+
+const WIDTH: f64 = 320.0;
+const HEIGHT: f64 = 120.0;
+
+fn points(
+    records: &[Record],
+    value: fn(&Record) -> Option<usize>,
+    first: u64,
+    span: u64,
+    max: usize,
+) -> String {
+    records
+        .iter()
+        .filter_map(|record| {
+            let x = (record.generation - first) as f64 / span as f64 * WIDTH;
+            let y = HEIGHT - value(record)? as f64 / max as f64 * HEIGHT;
+            Some(format!("{:.1},{:.1}", x, y))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Line chart of the population, births and deaths of the last generations, which can be
+/// downloaded as CSV.
+#[function_component(Graph)]
+pub fn graph() -> Html {
+    let universe = use_context::<MessageContext>().expect("no universe ctx found");
+    let visible = use_state(|| false);
+    let zoom = use_state(|| 1_usize);
+    let visible_clone = visible.clone();
+    let on_toggle_click = Callback::from(move |_| visible_clone.set(!*visible_clone));
+    if !*visible {
+        return html! {
+            <div class="fixed top-10 left-0 p-2 text-sm">
+                <button onclick={on_toggle_click} class="bg-gray-900 text-gray-300 rounded px-2 hover:bg-gray-700">
+                    {"graph"}
+                </button>
+            </div>
+        };
+    }
+    let zoom_clone = zoom.clone();
+    let on_zoom_in_click = Callback::from(move |_| zoom_clone.set((*zoom_clone).saturating_sub(1)));
+    let zoom_clone = zoom.clone();
+    let on_zoom_out_click =
+        Callback::from(move |_| zoom_clone.set((*zoom_clone + 1).min(ZOOMS.len() - 1)));
+    let history = &universe.history;
+    let last = history.latest().map_or(0, |record| record.generation);
+    let first = last.saturating_sub(ZOOMS[*zoom]);
+    let records = history.since(first).copied().collect::<Vec<_>>();
+    let span = (last - first).max(1);
+    let max = records
+        .iter()
+        .map(|record| {
+            record
+                .population
+                .max(record.births.unwrap_or(0))
+                .max(record.deaths.unwrap_or(0))
+        })
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let series = [
+        (
+            "stroke-yellow-400",
+            (|record: &Record| Some(record.population)) as fn(&Record) -> Option<usize>,
+        ),
+        ("stroke-green-400", |record: &Record| record.births),
+        ("stroke-red-400", |record: &Record| record.deaths),
+    ]
+    .iter()
+    .map(|(class, value)| {
+        html! {
+            <polyline class={classes!("fill-transparent", *class)} points={points(&records, *value, first, span, max)}/>
+        }
+    })
+    .collect::<Html>();
+    // the values only contain digits, commas and line feeds
+    let csv = format!(
+        "data:text/csv;charset=utf-8,{}",
+        history.to_csv().replace('\n', "%0A")
+    );
+    let button = "bg-gray-900 text-gray-300 rounded px-2 hover:bg-gray-700";
+    html! {
+        <div class="fixed top-10 left-0 p-2 space-y-1 text-sm text-gray-300">
+            <div class="flex space-x-2">
+                <button onclick={on_toggle_click} class={button}>{"graph"}</button>
+                <button onclick={on_zoom_in_click} class={button}>{"+"}</button>
+                <button onclick={on_zoom_out_click} class={button}>{"-"}</button>
+                <span>{format!("{} generations, max {}", ZOOMS[*zoom], max)}</span>
+                <a href={csv} download="population.csv" class={button}>{"csv"}</a>
+            </div>
+            <svg width={WIDTH.to_string()} height={HEIGHT.to_string()} class="bg-gray-900/75 rounded">
+                {series}
+            </svg>
+        </div>
+    }
+}
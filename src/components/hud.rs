@@ -0,0 +1,30 @@
+use super::universe_ctx::MessageContext;
+use yew::prelude::*;
+
+/// Overlay with the generation, the population, the cells born and dead in the last step, and
+/// the period of the cells once they repeat.
+#[function_component(Hud)]
+pub fn hud() -> Html {
+    let universe = use_context::<MessageContext>().expect("no universe ctx found");
+    let record = match universe.history.latest() {
+        Some(record) => *record,
+        None => return html! {},
+    };
+    html! {
+        <div class="flex fixed top-0 left-1/2 -translate-x-1/2 p-2 space-x-4 text-sm text-gray-300 pointer-events-none">
+            <span>{format!("generation {}", record.generation)}</span>
+            <span>{format!("population {}", record.population)}</span>
+            // unknown after a jump
+            if let (Some(births), Some(deaths)) = (record.births, record.deaths) {
+                <span class="text-green-400">{format!("+{}", births)}</span>
+                <span class="text-red-400">{format!("-{}", deaths)}</span>
+            }
+            {
+                match universe.period.behavior() {
+                    Some(behavior) => html! { <span class="text-yellow-400">{behavior.to_string()}</span> },
+                    None => html! {},
+                }
+            }
+        </div>
+    }
+}
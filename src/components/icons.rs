@@ -54,3 +54,13 @@ pub fn trash_icon(props: &IconProps) -> Html {
         </svg>
     }
 }
+
+#[function_component(DotIcon)]
+pub fn dot_icon(props: &IconProps) -> Html {
+    let class = &props.class;
+    html! {
+        <svg xmlns="http://www.w3.org/2000/svg" {class} viewBox="0 0 20 20" fill="currentColor">
+            <path fill-rule="evenodd" d="M10 18a8 8 0 100-16 8 8 0 000 16zm0-5a3 3 0 100-6 3 3 0 000 6z" clip-rule="evenodd" />
+        </svg>
+    }
+}
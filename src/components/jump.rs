@@ -0,0 +1,62 @@
+use super::universe_ctx::{Command, MessageContext};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// Numbers of generations run by the jump buttons.
+const JUMPS: [u64; 3] = [10, 100, 1000];
+
+/// The current generation, with buttons running many generations at once and a field to
+/// jump to a later generation, as far as the engines run without freezing the page.
+#[function_component(Jump)]
+pub fn jump() -> Html {
+    let universe = use_context::<MessageContext>().expect("no universe ctx found");
+    let generation = universe.engine().generation();
+    let limit = universe.step_limit();
+    // why the last target was rejected
+    let error: UseStateHandle<Option<String>> = use_state(|| None);
+    let buttons = JUMPS
+        .iter()
+        .map(|generations| {
+            let universe = universe.clone();
+            let generations = *generations;
+            let onclick = Callback::from(move |_| universe.dispatch(Command::Step(generations)));
+            html! {
+                <button {onclick} class="bg-gray-900 text-gray-300 rounded px-2 hover:bg-gray-700">
+                    {format!("+{}", generations)}
+                </button>
+            }
+        })
+        .collect::<Html>();
+    let error_clone = error.clone();
+    let onchange = Callback::from(move |e: Event| {
+        let input = e.target_unchecked_into::<HtmlInputElement>();
+        let message = match input.value().trim().parse::<u64>() {
+            Ok(target) if target < generation => {
+                format!("Generation {} is already past {}", generation, target)
+            }
+            Ok(target) if target - generation > limit => format!(
+                "This rule runs at most {} generations at once, not {}",
+                limit,
+                target - generation
+            ),
+            Ok(target) => {
+                error_clone.set(None);
+                universe.dispatch(Command::Step(target - generation));
+                return;
+            }
+            Err(error) => format!("Invalid generation '{}': {}", input.value(), error),
+        };
+        log::warn!("{}", message);
+        input.set_value(&generation.to_string());
+        error_clone.set(Some(message));
+    });
+    html! {
+        <div class="flex fixed bottom-0 left-0 p-2 space-x-2 text-sm">
+            <input class="bg-gray-900 text-gray-300 rounded px-1 w-24" value={generation.to_string()} {onchange}/>
+            {buttons}
+            if let Some(error) = &*error {
+                <span class="text-red-400">{error}</span>
+            }
+        </div>
+    }
+}
@@ -1,8 +1,14 @@
 pub mod actions;
 pub mod app;
 pub mod bean;
+pub mod canvas;
+pub mod census;
 pub mod existence;
+pub mod graph;
+pub mod hud;
 pub mod icons;
+pub mod jump;
+pub mod soup;
 pub mod universe_ctx;
 use yew::prelude::*;
 
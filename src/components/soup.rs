@@ -0,0 +1,84 @@
+use super::universe_ctx::{Command, MessageContext};
+use crate::universe::soup::{Soup, SYMMETRIES};
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+/// Sides of the soup region offered in the settings; `None` fills the window.
+const SIZES: [Option<i32>; 4] = [None, Some(16), Some(32), Some(64)];
+
+const DENSITIES: [u8; 6] = [5, 10, 20, 30, 40, 50];
+
+/// The seed and the settings of the soup, so a shuffle can be shared and generated again.
+#[function_component(SoupSettings)]
+pub fn soup_settings() -> Html {
+    let universe = use_context::<MessageContext>().expect("no universe ctx found");
+    let soup = universe.soup.clone();
+    let universe_clone = universe.clone();
+    let on_seed_change = Callback::from(move |e: Event| {
+        let input = e.target_unchecked_into::<HtmlInputElement>();
+        match input.value().trim().parse::<u64>() {
+            Ok(seed) => universe_clone.dispatch(Command::Shuffle(Some(seed))),
+            Err(error) => log::warn!("Invalid seed '{}': {}", input.value(), error),
+        }
+    });
+    let select = |update: fn(&mut Soup, &str)| {
+        let universe = universe.clone();
+        let soup = soup.clone();
+        Callback::from(move |e: Event| {
+            let mut soup = soup.clone();
+            update(
+                &mut soup,
+                &e.target_unchecked_into::<HtmlSelectElement>().value(),
+            );
+            universe.dispatch(Command::SetSoup(soup));
+        })
+    };
+    let on_density_change = select(|soup, value| {
+        soup.density = value.parse().unwrap_or(soup.density);
+    });
+    let on_size_change = select(|soup, value| soup.size = value.parse().ok());
+    let on_symmetry_change = select(|soup, value| {
+        soup.symmetry = value.parse().unwrap_or(soup.symmetry);
+    });
+    let densities = DENSITIES
+        .iter()
+        .map(|density| {
+            html! {
+                <option value={density.to_string()} selected={*density == soup.density}>
+                    {format!("{}%", density)}
+                </option>
+            }
+        })
+        .collect::<Html>();
+    let sizes = SIZES
+        .iter()
+        .map(|size| {
+            let (value, label) = match size {
+                Some(size) => (size.to_string(), format!("{}x{}", size, size)),
+                None => (String::new(), "window".to_string()),
+            };
+            html! {
+                <option {value} selected={*size == soup.size}>{label}</option>
+            }
+        })
+        .collect::<Html>();
+    let symmetries = SYMMETRIES
+        .iter()
+        .map(|symmetry| {
+            html! {
+                <option value={symmetry.to_string()} selected={*symmetry == soup.symmetry}>
+                    {symmetry.to_string()}
+                </option>
+            }
+        })
+        .collect::<Html>();
+    let field = "bg-gray-900 text-gray-300 rounded px-1";
+    html! {
+        <div class="flex fixed top-0 right-0 p-2 space-x-2 text-sm">
+            <input class={classes!(field, "w-48")} value={soup.seed.to_string()} onchange={on_seed_change}/>
+            <select class={field} onchange={on_density_change}>{densities}</select>
+            <select class={field} onchange={on_size_change}>{sizes}</select>
+            <select class={field} onchange={on_symmetry_change}>{symmetries}</select>
+        </div>
+    }
+}
@@ -1,24 +1,66 @@
 use super::Dimensions;
-use crate::universe::{Entity, Universe, CELL_SIZE};
-use rand::Rng;
+use crate::engine::hashlife::HashLife;
+use crate::engine::{EngineKind, LifeEngine};
+use crate::simulation::Simulation;
+use crate::universe::format::Format;
+use crate::universe::macrocell::Macrocell;
+use crate::universe::rule::Rule;
+use crate::universe::soup::Soup;
+use crate::universe::{Entity, ParseError, Universe, CELL_SIZE};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::convert::TryFrom;
 use std::rc::Rc;
 use yew::prelude::*;
 
-fn random_universe(universe: &mut Universe) {
-    universe.entities.clear();
+fn random_universe(engine: &mut dyn LifeEngine, soup: &Soup) {
     let window = window_dimensions();
-    let max_y = window.height / CELL_SIZE;
-    let max_x = window.width / CELL_SIZE;
-    let mut rng = rand::thread_rng();
-    for y in 0..max_y {
-        for x in 0..max_x {
-            let number = rng.gen_range(0..10);
-            if number == 7 {
-                add_entity(universe, x * CELL_SIZE, y * CELL_SIZE)
-            }
-        }
+    // finite grids are filled entirely, even if they do not fit in the window
+    let (width, height) = engine
+        .rule()
+        .topology()
+        .size()
+        .unwrap_or((window.width / CELL_SIZE, window.height / CELL_SIZE));
+    // elementary rules start from a single random row
+    let height = if engine.rule().elementary().is_some() {
+        1
+    } else {
+        height
+    };
+    let mut rng = StdRng::seed_from_u64(soup.seed);
+    engine.seed(soup.seed);
+    for (line, column) in soup.cells(width, height) {
+        let team = rng.gen_range(0..engine.rule().teams());
+        engine.set_team(line, column, team);
     }
-    universe.tick();
+    engine.step(1);
+}
+
+fn seed_universe(engine: &mut dyn LifeEngine) {
+    let window = window_dimensions();
+    let width = engine
+        .rule()
+        .topology()
+        .size()
+        .map_or(window.width / CELL_SIZE, |(width, _)| width);
+    engine.set_cell(0, width / 2, true);
+}
+
+fn center_universe(universe: &mut Universe) {
+    if universe.entities.is_empty() {
+        return;
+    }
+    let window = window_dimensions();
+    let boundaries = universe.boundaries();
+    let columns = (window.width / CELL_SIZE - (boundaries.column_max - boundaries.column_min)) / 2;
+    // the last row of elementary rules is the row of the current generation
+    if universe.rule.elementary().is_some() {
+        let line = i32::try_from(universe.generation).unwrap_or(i32::MAX);
+        universe.translate(line - boundaries.line_max, columns - boundaries.column_min);
+        return;
+    }
+    let lines = (window.height / CELL_SIZE - (boundaries.line_max - boundaries.line_min)) / 2;
+    universe.translate(lines - boundaries.line_min, columns - boundaries.column_min);
 }
 
 pub fn window_dimensions() -> Dimensions {
@@ -38,53 +80,149 @@ pub fn window_dimensions() -> Dimensions {
     Dimensions { height, width }
 }
 
-fn add_entity(universe: &mut Universe, x: i32, y: i32) {
-    let column = x / CELL_SIZE;
+fn cell_position(rule: &Rule, x: i32, y: i32) -> (i32, i32) {
     let line = y / CELL_SIZE;
-    let entity = Rc::new(Entity { line, column });
-    universe.entities.insert(entity);
+    let shift = rule.neighborhood().shift(line);
+    (line, (x - shift).div_euclid(CELL_SIZE))
+}
+
+fn add_entity(engine: &mut dyn LifeEngine, x: i32, y: i32, team: u8) {
+    let (line, column) = cell_position(engine.rule(), x, y);
+    if team < engine.rule().teams() {
+        engine.set_team(line, column, team);
+    } else {
+        engine.set_cell(line, column, true);
+    }
+}
+
+fn empty_universe(rule: &Rule) -> Universe {
+    let mut universe = Universe::new("");
+    universe.rule = rule.clone();
+    universe
+}
+
+fn shuffle(simulation: &mut Simulation) {
+    let empty = empty_universe(simulation.engine().rule());
+    simulation.load(&empty);
+    let soup = simulation.soup.clone();
+    random_universe(&mut *simulation.engine_mut(), &soup);
+    simulation.engine_mut().step(1);
+    simulation.reset_history();
 }
 
 pub enum Command {
-    Shuffle,
+    /// Fills the universe with a new soup, from the seed or from a random one.
+    Shuffle(Option<u64>),
+    /// Changes the soup settings, and fills the universe with the new soup.
+    SetSoup(Soup),
+    Seed,
     Clear,
     Tick,
+    /// Runs the given number of generations at once, only the last one being drawn.
+    Step(u64),
     Delete(Rc<Entity>),
-    Add { x: i32, y: i32 },
+    /// Adds a live cell at the position in pixels, of the team for rules with colored cells.
+    Add {
+        x: i32,
+        y: i32,
+        team: u8,
+    },
+    /// Removes the live cell at the position in pixels, or adds one as [`Command::Add`] does.
+    Toggle {
+        x: i32,
+        y: i32,
+        team: u8,
+    },
+    SetRule(Rule),
+    Load(Universe),
+    /// Loads the pattern on HashLife when it runs the rule, without listing the cells.
+    LoadMacrocell(Macrocell),
+    SetEngine(EngineKind),
+}
+
+/// The command loading a pattern in any format.
+pub fn load_command(value: &str) -> std::result::Result<Command, ParseError> {
+    if Format::detect(value) == Some(Format::Macrocell) {
+        Ok(Command::LoadMacrocell(Macrocell::parse(value)?))
+    } else {
+        Ok(Command::Load(Universe::from_pattern(value)?))
+    }
 }
 
-impl Reducible for Universe {
+impl Reducible for Simulation {
     type Action = Command;
 
     fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
-        Rc::new(match action {
-            Command::Shuffle => {
-                let mut universe = Universe::new("");
-                random_universe(&mut universe);
-                universe.tick();
-                universe
+        let mut simulation = (*self).clone();
+        match action {
+            Command::Shuffle(seed) => {
+                simulation.soup.seed = seed.unwrap_or_else(rand::random);
+                shuffle(&mut simulation);
+            }
+            Command::SetSoup(soup) => {
+                simulation.soup = soup;
+                shuffle(&mut simulation);
             }
-            Command::Clear => Universe::new(""),
-            Command::Tick => {
-                let mut universe = (*self).clone();
-                universe.tick();
-                universe
+            Command::Seed => {
+                let empty = empty_universe(simulation.engine().rule());
+                simulation.load(&empty);
+                seed_universe(&mut *simulation.engine_mut());
+                simulation.reset_history();
             }
+            Command::Clear => {
+                let empty = empty_universe(simulation.engine().rule());
+                simulation.load(&empty);
+            }
+            Command::Tick => simulation.step(1),
+            Command::Step(generations) => simulation.step(generations),
             Command::Delete(entity) => {
-                let mut universe = (*self).clone();
-                universe.entities.remove(&entity);
-                universe
+                simulation
+                    .engine_mut()
+                    .set_cell(entity.line, entity.column, false);
+                simulation.restart_period();
+            }
+            Command::Add { x, y, team } => {
+                add_entity(&mut *simulation.engine_mut(), x, y, team);
+                simulation.restart_period();
             }
-            Command::Add { x, y } => {
-                let mut universe = (*self).clone();
-                add_entity(&mut universe, x, y);
-                universe
+            Command::Toggle { x, y, team } => {
+                let mut engine = simulation.engine_mut();
+                let (line, column) = cell_position(engine.rule(), x, y);
+                if engine.get_cell(line, column) {
+                    engine.set_cell(line, column, false);
+                } else {
+                    add_entity(&mut *engine, x, y, team);
+                }
+                drop(engine);
+                simulation.restart_period();
             }
-        })
+            Command::SetRule(rule) => simulation.set_rule(rule),
+            Command::Load(mut universe) => {
+                center_universe(&mut universe);
+                simulation.load(&universe);
+            }
+            Command::LoadMacrocell(macrocell) if EngineKind::HashLife.supports(&macrocell.rule) => {
+                let mut hashlife = HashLife::from_macrocell(&macrocell);
+                // the root is centered on the origin, the top left corner of the window
+                let window = window_dimensions();
+                hashlife.translate(window.height / CELL_SIZE / 2, window.width / CELL_SIZE / 2);
+                simulation.load_hashlife(hashlife);
+            }
+            Command::LoadMacrocell(macrocell) => match macrocell.to_universe() {
+                Ok(mut universe) => {
+                    center_universe(&mut universe);
+                    simulation.load(&universe);
+                }
+                Err(error) => log::warn!("Unable to load pattern: {}", error),
+            },
+            Command::SetEngine(kind) => simulation.set_kind(kind),
+        }
+        simulation.touch();
+        Rc::new(simulation)
     }
 }
 
-pub type MessageContext = UseReducerHandle<Universe>;
+pub type MessageContext = UseReducerHandle<Simulation>;
 
 #[derive(Properties, Debug, PartialEq)]
 pub struct UniverseProviderProps {
@@ -94,15 +232,42 @@ pub struct UniverseProviderProps {
 
 #[function_component(UniverseProvider)]
 pub fn provider(props: &UniverseProviderProps) -> Html {
-    let universe = use_reducer(|| {
-        let mut universe = Universe::new("");
-        random_universe(&mut universe);
-        universe.tick();
-        universe
+    let simulation = use_reducer(|| {
+        let mut simulation = Simulation::new(EngineKind::Dense, &Universe::new(""));
+        simulation.soup.seed = rand::random();
+        shuffle(&mut simulation);
+        simulation
     });
     html! {
-        <ContextProvider<MessageContext> context={universe}>
+        <ContextProvider<MessageContext> context={simulation}>
             {props.children.clone()}
         </ContextProvider<MessageContext>>
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_engine() {
+        let simulation = Rc::new(Simulation::new(EngineKind::Universe, &Universe::new("111")));
+        let simulation = simulation.reduce(Command::SetEngine(EngineKind::Dense));
+        assert_eq!(EngineKind::Dense, simulation.kind);
+        // long jumps go to HashLife, whatever the chosen engine
+        let simulation = simulation.reduce(Command::Step(1_000_001));
+        assert_eq!(EngineKind::HashLife, simulation.kind);
+        assert_eq!(EngineKind::Dense, simulation.preferred);
+        assert_eq!(1_000_001, simulation.engine().generation());
+        assert_eq!(Universe::new("010\n010\n010"), {
+            let mut blinker = simulation.engine().to_universe();
+            blinker.translate(1, 0);
+            blinker
+        });
+        // an engine not supporting the rule is kept for later rules
+        let simulation = simulation.reduce(Command::SetEngine(EngineKind::Margolus));
+        assert_eq!(EngineKind::HashLife, simulation.kind);
+        let simulation = simulation.reduce(Command::SetRule(Rule::parse("BBM").unwrap()));
+        assert_eq!(EngineKind::Margolus, simulation.kind);
+    }
+}
@@ -0,0 +1,258 @@
+use crate::universe::neighborhood::Neighborhood;
+use crate::universe::rule::Rule;
+use crate::universe::topology::Topology;
+use crate::universe::{Boundaries, Entity, ParseError, Universe};
+use dense::Dense;
+use hashlife::HashLife;
+use margolus::Margolus;
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter, Result};
+use std::rc::Rc;
+use std::str::FromStr;
+
+/// Generations a single step runs at most on the engines computing them one by one, beyond
+/// which the page freezes.
+pub const STEP_LIMIT: u64 = 10_000;
+
+pub mod dense;
+pub mod hashlife;
+pub mod margolus;
+
+/// Operations shared by the simulation backends, so they can be swapped and compared.
+pub trait LifeEngine {
+    /// Advances the simulation by the given number of generations.
+    fn step(&mut self, generations: u64);
+    fn set_cell(&mut self, line: i32, column: i32, alive: bool);
+    fn get_cell(&self, line: i32, column: i32) -> bool;
+    /// Number of cells in a non-zero state.
+    fn population(&self) -> usize;
+    /// Generations run since the engine was created, counting from the generation of the
+    /// universe it was created from.
+    fn generation(&self) -> u64;
+    /// Live cells, ordered by line and then by column.
+    fn cells(&self) -> Vec<Entity>;
+    fn rule(&self) -> &Rule;
+    fn set_rule(&mut self, rule: Rule);
+    fn boxed_clone(&self) -> Box<dyn LifeEngine>;
+
+    /// State of the cell; engines without multi-state support only use 0 and 1.
+    fn get_state(&self, line: i32, column: i32) -> u8 {
+        self.get_cell(line, column) as u8
+    }
+
+    fn set_state(&mut self, line: i32, column: i32, state: u8) {
+        self.set_cell(line, column, state != 0);
+    }
+
+    /// Advances the simulation by one generation, and returns the numbers of cells born and
+    /// dead, or `None` when counting them would cost much more than the step.
+    fn step_counted(&mut self) -> Option<(usize, usize)> {
+        let before = self.cells().into_iter().collect::<BTreeSet<_>>();
+        self.step(1);
+        let after = self.cells().into_iter().collect::<BTreeSet<_>>();
+        Some((
+            after.difference(&before).count(),
+            before.difference(&after).count(),
+        ))
+    }
+
+    /// The most generations a single step should run, for the engine to stay responsive.
+    fn step_limit(&self) -> u64 {
+        STEP_LIMIT
+    }
+
+    /// Seeds the random numbers of stochastic rules; other engines ignore it.
+    fn seed(&mut self, _seed: u64) {}
+
+    /// Adds a live cell of the team, for rules with colored cells; other engines ignore it.
+    fn set_team(&mut self, line: i32, column: i32, _team: u8) {
+        self.set_cell(line, column, true);
+    }
+
+    /// The smallest rectangle containing all the live cells, if any.
+    fn bounding_box(&self) -> Option<Boundaries> {
+        let cells = self.cells();
+        let first = cells.first()?;
+        let last = cells.last()?;
+        Some(Boundaries {
+            line_min: first.line,
+            line_max: last.line,
+            column_min: cells.iter().map(|e| e.column).min()?,
+            column_max: cells.iter().map(|e| e.column).max()?,
+        })
+    }
+
+    /// Live cells inside the rectangle (inclusive), ordered by line and then by column.
+    fn cells_in_rect(&self, rect: &Boundaries) -> Vec<Entity> {
+        self.cells()
+            .into_iter()
+            .filter(|entity| rect.contains(entity.line, entity.column))
+            .collect()
+    }
+
+    fn to_universe(&self) -> Universe {
+        let mut universe = Universe::new("");
+        universe.rule = self.rule().clone();
+        universe.generation = self.generation();
+        universe.entities = self.cells().into_iter().map(Rc::new).collect();
+        universe
+    }
+}
+
+impl Clone for Box<dyn LifeEngine> {
+    fn clone(&self) -> Self {
+        self.boxed_clone()
+    }
+}
+
+/// The available [`LifeEngine`] implementations.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum EngineKind {
+    Universe,
+    HashLife,
+    Dense,
+    Margolus,
+}
+
+pub const ENGINE_KINDS: [EngineKind; 4] = [
+    EngineKind::Universe,
+    EngineKind::HashLife,
+    EngineKind::Dense,
+    EngineKind::Margolus,
+];
+
+impl EngineKind {
+    /// Whether the engine can run the rule.
+    pub fn supports(&self, rule: &Rule) -> bool {
+        match self {
+            EngineKind::Universe => rule.block().is_none(),
+            EngineKind::Margolus => rule.block().is_some(),
+            EngineKind::HashLife | EngineKind::Dense => {
+                rule.topology() == &Topology::Plane
+                    && rule.states() == 2
+                    && rule.neighborhood() == &Neighborhood::Moore
+                    && rule.is_totalistic()
+                    && rule.radius() == 1
+                    && rule.teams() == 1
+                    && rule.stochastic().is_none()
+            }
+        }
+    }
+
+    pub fn create(&self, universe: &Universe) -> Box<dyn LifeEngine> {
+        match self {
+            EngineKind::Universe => Box::new(universe.clone()),
+            EngineKind::HashLife => Box::new(HashLife::from_universe(universe)),
+            EngineKind::Dense => Box::new(Dense::from_universe(universe)),
+            EngineKind::Margolus => Box::new(Margolus::from_universe(universe)),
+        }
+    }
+
+    /// The engine running any rule of its family, used when the current one does not support
+    /// a rule.
+    pub fn fallback(rule: &Rule) -> Self {
+        if rule.block().is_some() {
+            EngineKind::Margolus
+        } else {
+            EngineKind::Universe
+        }
+    }
+}
+
+impl Display for EngineKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for EngineKind {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        ENGINE_KINDS
+            .iter()
+            .find(|kind| kind.to_string().eq_ignore_ascii_case(value.trim()))
+            .copied()
+            .ok_or_else(|| ParseError::new(format!("unknown engine '{}'", value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_engines_agree() {
+        let universe = Universe::from_rle(
+            r#"x = 36, y = 9, rule = B3/S23
+24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b
+obo$10bo5bo7bo$11bo3bo$12b2o!"#,
+        )
+        .unwrap();
+        let mut engines: Vec<Box<dyn LifeEngine>> = [
+            EngineKind::Universe,
+            EngineKind::HashLife,
+            EngineKind::Dense,
+        ]
+        .iter()
+        .map(|kind| kind.create(&universe))
+        .collect();
+        let rect = Boundaries {
+            line_min: 2,
+            line_max: 20,
+            column_min: 10,
+            column_max: 30,
+        };
+        for engine in engines.iter_mut() {
+            engine.step(60);
+        }
+        let expected = engines[0].to_universe();
+        for engine in &engines {
+            assert_eq!(expected, engine.to_universe());
+            assert_eq!(engines[0].population(), engine.population());
+            assert_eq!(engines[0].bounding_box(), engine.bounding_box());
+            assert_eq!(engines[0].cells_in_rect(&rect), engine.cells_in_rect(&rect));
+        }
+        assert!(engines[0]
+            .cells_in_rect(&rect)
+            .iter()
+            .all(|e| rect.contains(e.line, e.column)));
+        assert_ne!(engines[0].cells(), engines[0].cells_in_rect(&rect));
+    }
+
+    #[test]
+    fn test_random_soup() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+        let mut random = StdRng::seed_from_u64(7);
+        let mut soup = Universe::new("");
+        for line in 0..40 {
+            for column in 0..40 {
+                if random.gen_range(0..10) < 3 {
+                    soup.set_cell(line, column, true);
+                }
+            }
+        }
+        for rule in ["B3/S23", "B36/S23", "B2/S", "B3678/S34678"] {
+            soup.set_rule(Rule::parse(rule).unwrap());
+            let mut expected = soup.clone();
+            expected.step(30);
+            for kind in &ENGINE_KINDS {
+                if kind.supports(&soup.rule) {
+                    let mut engine = kind.create(&soup);
+                    engine.step(30);
+                    assert_eq!(expected, engine.to_universe(), "{} rule {}", kind, rule);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_kind() {
+        for kind in &ENGINE_KINDS {
+            assert_eq!(Ok(*kind), kind.to_string().parse());
+        }
+        assert_eq!(Ok(EngineKind::HashLife), "hashlife".parse());
+        assert!("Quadtree".parse::<EngineKind>().is_err());
+    }
+}
@@ -0,0 +1,417 @@
+use crate::engine::{LifeEngine, STEP_LIMIT};
+use crate::universe::rule::Rule;
+use crate::universe::{Boundaries, Entity, Universe};
+
+const WORD_BITS: i32 = 64;
+const LINE_MARGIN: usize = 8;
+
+/// Dense grid storing each line as packed `u64` words, one bit per cell.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Dense {
+    rule: Rule,
+    // line and column of the top left cell
+    line: i32,
+    column: i32,
+    words: usize,
+    lines: usize,
+    cells: Vec<u64>,
+    generation: u64,
+}
+
+fn add(sum: &mut [u64; 4], bits: u64) {
+    let mut carry = bits;
+    for digit in sum.iter_mut() {
+        let next_carry = *digit & carry;
+        *digit ^= carry;
+        carry = next_carry;
+    }
+}
+
+impl Dense {
+    pub fn new(rule: Rule) -> Self {
+        Dense {
+            rule,
+            line: 0,
+            column: 0,
+            words: 0,
+            lines: 0,
+            cells: vec![],
+            generation: 0,
+        }
+    }
+
+    pub fn from_universe(universe: &Universe) -> Self {
+        let mut dense = Dense::new(universe.rule.clone());
+        dense.generation = universe.generation;
+        for entity in &universe.entities {
+            dense.set_cell(entity.line, entity.column, true);
+        }
+        dense
+    }
+
+    fn row(&self, line: usize) -> &[u64] {
+        &self.cells[line * self.words..(line + 1) * self.words]
+    }
+
+    fn grow(&mut self, top: usize, bottom: usize, left: usize, right: usize) {
+        let words = self.words + left + right;
+        let lines = self.lines + top + bottom;
+        let mut cells = vec![0; words * lines];
+        for line in 0..self.lines {
+            let start = (line + top) * words + left;
+            cells[start..start + self.words].copy_from_slice(self.row(line));
+        }
+        self.line -= top as i32;
+        self.column -= left as i32 * WORD_BITS;
+        self.words = words;
+        self.lines = lines;
+        self.cells = cells;
+    }
+
+    fn ensure_margin(&mut self) {
+        if self.lines == 0 {
+            return;
+        }
+        let last = self.lines - 1;
+        let top = self.row(0).iter().any(|word| *word != 0);
+        let bottom = self.row(last).iter().any(|word| *word != 0);
+        let left = (0..self.lines).any(|line| self.row(line)[0] & 1 != 0);
+        let right = (0..self.lines).any(|line| self.row(line)[self.words - 1] >> 63 != 0);
+        if top || bottom || left || right {
+            self.grow(
+                if top { LINE_MARGIN } else { 0 },
+                if bottom { LINE_MARGIN } else { 0 },
+                left as usize,
+                right as usize,
+            );
+        }
+    }
+
+    fn trim(&mut self) {
+        let live = |line: &usize| self.row(*line).iter().any(|word| *word != 0);
+        let (first, last) = match (0..self.lines).position(|line| live(&line)) {
+            Some(first) => (
+                first,
+                (0..self.lines).rposition(|line| live(&line)).unwrap(),
+            ),
+            None => {
+                self.lines = 0;
+                self.words = 0;
+                self.cells.clear();
+                return;
+            }
+        };
+        let live = |word: &usize| (first..=last).any(|line| self.row(line)[*word] != 0);
+        let first_word = (0..self.words).position(|word| live(&word)).unwrap();
+        let last_word = (0..self.words).rposition(|word| live(&word)).unwrap();
+        if first <= 2 * LINE_MARGIN
+            && self.lines - 1 - last <= 2 * LINE_MARGIN
+            && first_word <= 2
+            && self.words - 1 - last_word <= 2
+        {
+            return;
+        }
+        let top = first.saturating_sub(LINE_MARGIN);
+        let bottom = (last + LINE_MARGIN + 1).min(self.lines);
+        let left = first_word.saturating_sub(1);
+        let right = (last_word + 2).min(self.words);
+        let words = right - left;
+        let mut cells = Vec::with_capacity(words * (bottom - top));
+        for line in top..bottom {
+            cells.extend_from_slice(&self.row(line)[left..right]);
+        }
+        self.line += top as i32;
+        self.column += left as i32 * WORD_BITS;
+        self.words = words;
+        self.lines = bottom - top;
+        self.cells = cells;
+    }
+
+    fn tick(&mut self) -> (usize, usize) {
+        self.trim();
+        self.ensure_margin();
+        let mut birth = [false; 9];
+        let mut survival = [false; 9];
+        for neighbors in 0..9 {
+            birth[neighbors] = self.rule.next(false, neighbors);
+            survival[neighbors] = self.rule.next(true, neighbors);
+        }
+        let empty = vec![0; self.words];
+        let mut cells = vec![0; self.cells.len()];
+        let (mut births, mut deaths) = (0, 0);
+        for line in 0..self.lines {
+            let above = if line > 0 { self.row(line - 1) } else { &empty };
+            let current = self.row(line);
+            let below = if line + 1 < self.lines {
+                self.row(line + 1)
+            } else {
+                &empty
+            };
+            for word in 0..self.words {
+                let mut sum = [0; 4];
+                for (row, include_center) in [(above, true), (current, false), (below, true)] {
+                    let previous = if word > 0 { row[word - 1] } else { 0 };
+                    let next = if word + 1 < self.words {
+                        row[word + 1]
+                    } else {
+                        0
+                    };
+                    add(&mut sum, (row[word] << 1) | (previous >> 63));
+                    add(&mut sum, (row[word] >> 1) | (next << 63));
+                    if include_center {
+                        add(&mut sum, row[word]);
+                    }
+                }
+                let alive = current[word];
+                let mut result = 0;
+                for neighbors in 0..9 {
+                    let rule = match (birth[neighbors], survival[neighbors]) {
+                        (true, true) => u64::MAX,
+                        (true, false) => !alive,
+                        (false, true) => alive,
+                        (false, false) => continue,
+                    };
+                    let count = sum.iter().enumerate().fold(u64::MAX, |mask, (bit, digit)| {
+                        if neighbors & (1 << bit) != 0 {
+                            mask & digit
+                        } else {
+                            mask & !digit
+                        }
+                    });
+                    result |= rule & count;
+                }
+                births += (result & !alive).count_ones() as usize;
+                deaths += (alive & !result).count_ones() as usize;
+                cells[line * self.words + word] = result;
+            }
+        }
+        self.cells = cells;
+        (births, deaths)
+    }
+
+    fn position(&self, line: i32, column: i32) -> Option<(usize, usize, u64)> {
+        let line = line - self.line;
+        let column = column - self.column;
+        if line < 0 || column < 0 || line as usize >= self.lines {
+            return None;
+        }
+        let word = (column / WORD_BITS) as usize;
+        if word >= self.words {
+            return None;
+        }
+        Some((line as usize, word, 1 << (column % WORD_BITS)))
+    }
+}
+
+impl LifeEngine for Dense {
+    fn step(&mut self, generations: u64) {
+        for _ in 0..generations {
+            self.tick();
+        }
+        self.generation += generations;
+    }
+
+    fn step_counted(&mut self) -> Option<(usize, usize)> {
+        let changes = self.tick();
+        self.generation += 1;
+        Some(changes)
+    }
+
+    fn set_cell(&mut self, line: i32, column: i32, alive: bool) {
+        if self.lines == 0 {
+            if !alive {
+                return;
+            }
+            self.line = line;
+            self.column = column - column.rem_euclid(WORD_BITS);
+            self.lines = 1;
+            self.words = 1;
+            self.cells = vec![0];
+        }
+        let top = (self.line - line).max(0) as usize;
+        let bottom = (line - (self.line + self.lines as i32 - 1)).max(0) as usize;
+        let left = (self.column - column + WORD_BITS - 1).max(0) / WORD_BITS;
+        let right_edge = self.column + self.words as i32 * WORD_BITS;
+        let right = (column - right_edge + WORD_BITS).max(0) / WORD_BITS;
+        if top + bottom + left as usize + right as usize > 0 {
+            if !alive {
+                return;
+            }
+            self.grow(top, bottom, left as usize, right as usize);
+        }
+        let (line, word, mask) = self
+            .position(line, column)
+            .expect("the grid should contain the cell");
+        if alive {
+            self.cells[line * self.words + word] |= mask;
+        } else {
+            self.cells[line * self.words + word] &= !mask;
+        }
+    }
+
+    fn get_cell(&self, line: i32, column: i32) -> bool {
+        match self.position(line, column) {
+            Some((line, word, mask)) => self.cells[line * self.words + word] & mask != 0,
+            None => false,
+        }
+    }
+
+    fn population(&self) -> usize {
+        self.cells
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn cells(&self) -> Vec<Entity> {
+        self.cells_in_rect(&Boundaries {
+            line_min: self.line,
+            line_max: self.line + self.lines as i32 - 1,
+            column_min: self.column,
+            column_max: self.column + self.words as i32 * WORD_BITS - 1,
+        })
+    }
+
+    fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+    }
+
+    fn boxed_clone(&self) -> Box<dyn LifeEngine> {
+        Box::new(self.clone())
+    }
+
+    fn step_limit(&self) -> u64 {
+        // a word holds 64 cells, each generation is much faster than on the plain universe
+        10 * STEP_LIMIT
+    }
+
+    fn cells_in_rect(&self, rect: &Boundaries) -> Vec<Entity> {
+        let mut cells = vec![];
+        let first_line = (rect.line_min - self.line).max(0) as usize;
+        let last_line = ((rect.line_max - self.line + 1).max(0) as usize).min(self.lines);
+        let first_word = ((rect.column_min - self.column).max(0) / WORD_BITS) as usize;
+        let last_word =
+            (((rect.column_max - self.column).max(-1) / WORD_BITS + 1) as usize).min(self.words);
+        for line in first_line..last_line {
+            for index in first_word..last_word {
+                let mut word = self.row(line)[index];
+                while word != 0 {
+                    let column =
+                        self.column + index as i32 * WORD_BITS + word.trailing_zeros() as i32;
+                    if rect.contains(self.line + line as i32, column) {
+                        cells.push(Entity {
+                            line: self.line + line as i32,
+                            column,
+                            state: 1,
+                            team: 0,
+                        });
+                    }
+                    word &= word - 1;
+                }
+            }
+        }
+        cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_get_cell() {
+        let mut dense = Dense::new(Rule::default());
+        dense.set_cell(3, 70, true);
+        dense.set_cell(-10, -200, true);
+        dense.set_cell(100, 0, true);
+        dense.set_cell(50, 1000, false);
+        assert_eq!(3, dense.population());
+        assert!(dense.get_cell(3, 70));
+        assert!(dense.get_cell(-10, -200));
+        assert!(!dense.get_cell(-10, -199));
+        assert!(!dense.get_cell(50, 1000));
+        dense.set_cell(3, 70, false);
+        assert_eq!(
+            vec![
+                Entity {
+                    line: -10,
+                    column: -200,
+                    state: 1,
+                    team: 0
+                },
+                Entity {
+                    line: 100,
+                    column: 0,
+                    state: 1,
+                    team: 0
+                }
+            ],
+            dense.cells()
+        );
+    }
+
+    #[test]
+    fn test_glider_crossing_words() {
+        let mut universe = Universe::new(
+            r#"
+010
+001
+111
+        "#,
+        );
+        universe.translate(0, 60);
+        let mut dense = Dense::from_universe(&universe);
+        for _ in 0..40 {
+            universe.step(1);
+            dense.step(1);
+            assert_eq!(universe.cells(), dense.cells());
+        }
+        // moving up and left
+        let mut universe = Universe::new(
+            r#"
+111
+100
+010
+        "#,
+        );
+        universe.translate(-5, -200);
+        let mut dense = Dense::from_universe(&universe);
+        universe.step(40);
+        dense.step(40);
+        assert_eq!(universe, dense.to_universe());
+    }
+
+    #[test]
+    fn test_escaping_glider() {
+        let glider = Universe::new(
+            r#"
+010
+001
+111
+        "#,
+        );
+        let mut dense = Dense::from_universe(&glider);
+        for _ in 0..1000 {
+            dense.step(1);
+            assert!(dense.lines <= 3 + 6 * LINE_MARGIN, "{} lines", dense.lines);
+            assert!(dense.words <= 4, "{} words", dense.words);
+        }
+        let mut expected = glider;
+        expected.translate(250, 250);
+        assert_eq!(expected, dense.to_universe());
+        // nothing is left of a dying pattern
+        let mut dense = Dense::from_universe(&Universe::new("1"));
+        dense.step(2);
+        assert_eq!((0, 0), (dense.lines, dense.words));
+        dense.set_cell(5, 5, true);
+        assert_eq!(1, dense.population());
+    }
+}
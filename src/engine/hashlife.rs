@@ -0,0 +1,680 @@
+use crate::engine::LifeEngine;
+use crate::universe::macrocell::{Macrocell, Node as MacrocellNode};
+use crate::universe::rule::Rule;
+use crate::universe::{Boundaries, Entity, Universe};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+const MIN_LEVEL: u8 = 3;
+/// Level of the largest root, whose coordinates still fit in an `i64`.
+const MAX_LEVEL: u8 = 62;
+/// Largest step of [`HashLife::advance`], `2^MAX_STEP` generations at once.
+const MAX_STEP: u8 = MAX_LEVEL - 3;
+/// Nodes created before the ones the root does not use are dropped, with the memoised results.
+const NODE_LIMIT: usize = 1 << 19;
+
+#[derive(Eq, PartialEq, Debug, Clone)]
+struct Node {
+    level: u8,
+    // nw, ne, sw, se
+    children: [NodeId; 4],
+    population: u64,
+}
+
+/// Quadtree simulator implementing Gosper's HashLife algorithm.
+#[derive(Debug, Clone)]
+pub struct HashLife {
+    rule: Rule,
+    nodes: Vec<Node>,
+    index: HashMap<[NodeId; 4], NodeId>,
+    empty: Vec<NodeId>,
+    results: HashMap<(NodeId, u8), NodeId>,
+    root: NodeId,
+    generation: u64,
+    // number of nodes which triggers the next garbage collection
+    limit: usize,
+}
+
+impl HashLife {
+    pub fn new(rule: Rule) -> Self {
+        let leaf = |population| Node {
+            level: 0,
+            children: [DEAD; 4],
+            population,
+        };
+        let mut hashlife = HashLife {
+            rule,
+            nodes: vec![leaf(0), leaf(1)],
+            index: HashMap::new(),
+            empty: vec![DEAD],
+            results: HashMap::new(),
+            root: DEAD,
+            generation: 0,
+            limit: NODE_LIMIT,
+        };
+        hashlife.root = hashlife.empty(MIN_LEVEL);
+        hashlife
+    }
+
+    pub fn from_universe(universe: &Universe) -> Self {
+        let mut hashlife = HashLife::new(universe.rule.clone());
+        hashlife.generation = universe.generation;
+        for entity in &universe.entities {
+            hashlife.set_cell(entity.line, entity.column, true);
+        }
+        hashlife
+    }
+
+    /// Builds the tree from the nodes of the pattern, without listing its cells.
+    pub fn from_macrocell(macrocell: &Macrocell) -> Self {
+        let mut hashlife = HashLife::new(macrocell.rule.clone());
+        hashlife.generation = macrocell.generation;
+        let (root, level) = macrocell.root();
+        let mut copies = HashMap::new();
+        hashlife.root = hashlife.read(macrocell, root, level as u8, &mut copies);
+        while hashlife.level(hashlife.root) < MIN_LEVEL {
+            hashlife.expand();
+        }
+        hashlife.shrink();
+        hashlife
+    }
+
+    fn read(
+        &mut self,
+        macrocell: &Macrocell,
+        id: usize,
+        level: u8,
+        copies: &mut HashMap<usize, NodeId>,
+    ) -> NodeId {
+        if let Some(copy) = copies.get(&id) {
+            return *copy;
+        }
+        let copy = match macrocell.node(id) {
+            None => self.empty(level),
+            Some(MacrocellNode::Leaf(cells)) => {
+                let mut leaf = self.empty(level);
+                for (line, column, _) in cells {
+                    leaf = self.set(leaf, *line, *column, true);
+                }
+                leaf
+            }
+            Some(MacrocellNode::States(states)) => {
+                self.join(states.map(|state| if state == 0 { DEAD } else { ALIVE }))
+            }
+            Some(MacrocellNode::Inner { children, .. }) => {
+                let mut quadrants = [DEAD; 4];
+                for (quadrant, child) in quadrants.iter_mut().zip(children.iter()) {
+                    *quadrant = self.read(macrocell, *child, level - 1, copies);
+                }
+                self.join(quadrants)
+            }
+        };
+        // empty nodes of every level share the index 0
+        if id != 0 {
+            copies.insert(id, copy);
+        }
+        copy
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id as usize]
+    }
+
+    fn level(&self, id: NodeId) -> u8 {
+        self.node(id).level
+    }
+
+    fn children(&self, id: NodeId) -> [NodeId; 4] {
+        self.node(id).children
+    }
+
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(id) = self.index.get(&children) {
+            return *id;
+        }
+        let level = self.level(children[0]) + 1;
+        let population = children.iter().map(|c| self.node(*c).population).sum();
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            level,
+            children,
+            population,
+        });
+        self.index.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let empty = *self.empty.last().expect("empty collection?");
+            let id = self.join([empty; 4]);
+            self.empty.push(id);
+        }
+        self.empty[level as usize]
+    }
+
+    fn half(&self) -> i64 {
+        1 << (self.level(self.root) - 1)
+    }
+
+    fn expand(&mut self) {
+        let [nw, ne, sw, se] = self.children(self.root);
+        let empty = self.empty(self.level(self.root) - 1);
+        let nw = self.join([empty, empty, empty, nw]);
+        let ne = self.join([empty, empty, ne, empty]);
+        let sw = self.join([empty, sw, empty, empty]);
+        let se = self.join([se, empty, empty, empty]);
+        self.root = self.join([nw, ne, sw, se]);
+    }
+
+    fn center(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(id);
+        self.join([
+            self.children(nw)[3],
+            self.children(ne)[2],
+            self.children(sw)[1],
+            self.children(se)[0],
+        ])
+    }
+
+    fn fits_in_center(&self) -> bool {
+        let [nw, ne, sw, se] = self.children(self.root);
+        let inner = [(nw, 3), (ne, 2), (sw, 1), (se, 0)]
+            .iter()
+            .map(|(child, corner)| {
+                let grandchild = self.children(*child)[*corner];
+                self.node(self.children(grandchild)[*corner]).population
+            })
+            .sum::<u64>();
+        inner == self.node(self.root).population
+    }
+
+    fn shrink(&mut self) {
+        while self.level(self.root) > MIN_LEVEL {
+            let center = self.center(self.root);
+            if self.node(center).population != self.node(self.root).population {
+                return;
+            }
+            self.root = center;
+        }
+    }
+
+    fn base_case(&mut self, id: NodeId) -> NodeId {
+        let mut grid = [[false; 4]; 4];
+        for (quadrant, child) in self.children(id).iter().enumerate() {
+            for (index, leaf) in self.children(*child).iter().enumerate() {
+                let line = (quadrant / 2) * 2 + index / 2;
+                let column = (quadrant % 2) * 2 + index % 2;
+                grid[line][column] = *leaf == ALIVE;
+            }
+        }
+        let mut result = [DEAD; 4];
+        for (index, (line, column)) in [(1, 1), (1, 2), (2, 1), (2, 2)].iter().enumerate() {
+            let neighbors = (line - 1..=line + 1)
+                .flat_map(|l| (column - 1..=column + 1).map(move |c| (l, c)))
+                .filter(|(l, c)| (*l, *c) != (*line, *column) && grid[*l][*c])
+                .count();
+            if self.rule.next(grid[*line][*column], neighbors) {
+                result[index] = ALIVE;
+            }
+        }
+        self.join(result)
+    }
+
+    fn successor(&mut self, id: NodeId, step: u8) -> NodeId {
+        let level = self.level(id);
+        if self.node(id).population == 0 {
+            return self.empty(level - 1);
+        }
+        if let Some(result) = self.results.get(&(id, step)) {
+            return *result;
+        }
+        let result = if level == 2 {
+            self.base_case(id)
+        } else {
+            let [nw, ne, sw, se] = self.children(id);
+            let [_, nw_ne, nw_sw, nw_se] = self.children(nw);
+            let [ne_nw, _, ne_sw, ne_se] = self.children(ne);
+            let [sw_nw, sw_ne, _, sw_se] = self.children(sw);
+            let [se_nw, se_ne, se_sw, _] = self.children(se);
+            let top = self.join([nw_ne, ne_nw, nw_se, ne_sw]);
+            let left = self.join([nw_sw, nw_se, sw_nw, sw_ne]);
+            let center = self.join([nw_se, ne_sw, sw_ne, se_nw]);
+            let right = self.join([ne_sw, ne_se, se_nw, se_ne]);
+            let bottom = self.join([sw_ne, se_nw, sw_se, se_sw]);
+            let mut parts = [nw, top, ne, left, center, right, sw, bottom, se];
+            let full_speed = step == level - 2;
+            for part in parts.iter_mut() {
+                *part = if full_speed {
+                    self.successor(*part, level - 3)
+                } else {
+                    self.center(*part)
+                };
+            }
+            let next_step = if full_speed { level - 3 } else { step };
+            let quadrants = [
+                [parts[0], parts[1], parts[3], parts[4]],
+                [parts[1], parts[2], parts[4], parts[5]],
+                [parts[3], parts[4], parts[6], parts[7]],
+                [parts[4], parts[5], parts[7], parts[8]],
+            ];
+            let mut result = [DEAD; 4];
+            for (index, quadrant) in quadrants.iter().enumerate() {
+                let quadrant = self.join(*quadrant);
+                result[index] = self.successor(quadrant, next_step);
+            }
+            self.join(result)
+        };
+        self.results.insert((id, step), result);
+        result
+    }
+
+    fn copy(
+        &mut self,
+        other: &HashLife,
+        id: NodeId,
+        copies: &mut HashMap<NodeId, NodeId>,
+    ) -> NodeId {
+        if id == DEAD || id == ALIVE {
+            return id;
+        }
+        if let Some(copy) = copies.get(&id) {
+            return *copy;
+        }
+        let mut children = other.children(id);
+        for child in children.iter_mut() {
+            *child = self.copy(other, *child, copies);
+        }
+        let copy = self.join(children);
+        copies.insert(id, copy);
+        copy
+    }
+
+    fn collect_garbage(&mut self) {
+        let mut tree = HashLife::new(self.rule.clone());
+        tree.root = tree.copy(self, self.root, &mut HashMap::new());
+        tree.generation = self.generation;
+        // a root with many nodes is not collected again until it has created as many
+        tree.limit = NODE_LIMIT.max(2 * tree.nodes.len());
+        *self = tree;
+    }
+
+    fn advance(&mut self, step: u8) {
+        debug_assert!(step <= MAX_STEP);
+        if self.nodes.len() > self.limit {
+            self.collect_garbage();
+        }
+        while self.level(self.root) < step + 3
+            || (self.level(self.root) < MAX_LEVEL && !self.fits_in_center())
+        {
+            self.expand();
+        }
+        self.root = self.successor(self.root, step);
+        self.shrink();
+    }
+
+    /// Moves all the cells by the given numbers of lines and columns.
+    pub fn translate(&mut self, lines: i32, columns: i32) {
+        let distance = i64::from(lines.unsigned_abs().max(columns.unsigned_abs()));
+        while !self.fits_in_center() || self.half() / 4 < distance {
+            self.expand();
+        }
+        // the root of the level below, centered on the moved cells
+        let quarter = self.half() / 2;
+        let level = self.level(self.root) - 1;
+        let (line, column) = (quarter - i64::from(lines), quarter - i64::from(columns));
+        self.root = self.window(self.root, line, column, level, &mut HashMap::new());
+        while self.level(self.root) < MIN_LEVEL {
+            self.expand();
+        }
+        self.shrink();
+    }
+
+    fn window(
+        &mut self,
+        id: NodeId,
+        line: i64,
+        column: i64,
+        level: u8,
+        windows: &mut HashMap<(NodeId, i64, i64, u8), NodeId>,
+    ) -> NodeId {
+        if self.level(id) == level {
+            return id;
+        }
+        if self.node(id).population == 0 {
+            return self.empty(level);
+        }
+        if let Some(window) = windows.get(&(id, line, column, level)) {
+            return *window;
+        }
+        let half = 1 << (self.level(id) - 1);
+        let last = (1 << level) - 1;
+        let window = if line / half == (line + last) / half
+            && column / half == (column + last) / half
+        {
+            let index = (line >= half) as usize * 2 + (column >= half) as usize;
+            let child = self.children(id)[index];
+            self.window(child, line % half, column % half, level, windows)
+        } else {
+            // the window straddles the children, its quadrants are taken one by one
+            let quarter = 1 << (level - 1);
+            let mut quadrants = [DEAD; 4];
+            for (index, quadrant) in quadrants.iter_mut().enumerate() {
+                let (lines, columns) = ((index / 2) as i64 * quarter, (index % 2) as i64 * quarter);
+                *quadrant = self.window(id, line + lines, column + columns, level - 1, windows);
+            }
+            self.join(quadrants)
+        };
+        windows.insert((id, line, column, level), window);
+        window
+    }
+
+    fn set(&mut self, id: NodeId, line: i64, column: i64, alive: bool) -> NodeId {
+        let level = self.level(id);
+        if level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+        let half = 1 << (level - 1);
+        let index = (line >= half) as usize * 2 + (column >= half) as usize;
+        let mut children = self.children(id);
+        children[index] = self.set(children[index], line % half, column % half, alive);
+        self.join(children)
+    }
+
+    fn collect(
+        &self,
+        id: NodeId,
+        line: i64,
+        column: i64,
+        rect: &[i64; 4],
+        cells: &mut Vec<Entity>,
+    ) {
+        let node = self.node(id);
+        let size = 1 << node.level;
+        let [line_min, line_max, column_min, column_max] = *rect;
+        if node.population == 0
+            || line > line_max
+            || column > column_max
+            || line + size <= line_min
+            || column + size <= column_min
+        {
+            return;
+        }
+        if node.level == 0 {
+            if let (Ok(line), Ok(column)) = (i32::try_from(line), i32::try_from(column)) {
+                cells.push(Entity {
+                    line,
+                    column,
+                    state: 1,
+                    team: 0,
+                });
+            }
+            return;
+        }
+        let half = size / 2;
+        let [nw, ne, sw, se] = node.children;
+        self.collect(nw, line, column, rect, cells);
+        self.collect(ne, line, column + half, rect, cells);
+        self.collect(sw, line + half, column, rect, cells);
+        self.collect(se, line + half, column + half, rect, cells);
+    }
+}
+
+impl LifeEngine for HashLife {
+    fn step(&mut self, generations: u64) {
+        // the largest steps are split, so the root never grows beyond its maximum level
+        for _ in 0..generations >> MAX_STEP {
+            self.advance(MAX_STEP);
+        }
+        for step in 0..MAX_STEP {
+            if generations & (1 << step) != 0 {
+                self.advance(step);
+            }
+        }
+        self.generation = self.generation.saturating_add(generations);
+    }
+
+    fn step_counted(&mut self) -> Option<(usize, usize)> {
+        // the cells would have to be listed
+        self.step(1);
+        None
+    }
+
+    fn set_cell(&mut self, line: i32, column: i32, alive: bool) {
+        let (line, column) = (i64::from(line), i64::from(column));
+        while !(-self.half()..self.half()).contains(&line)
+            || !(-self.half()..self.half()).contains(&column)
+        {
+            self.expand();
+        }
+        let half = self.half();
+        self.root = self.set(self.root, line + half, column + half, alive);
+    }
+
+    fn get_cell(&self, line: i32, column: i32) -> bool {
+        let half = self.half();
+        let (mut line, mut column) = (i64::from(line) + half, i64::from(column) + half);
+        if !(0..half * 2).contains(&line) || !(0..half * 2).contains(&column) {
+            return false;
+        }
+        let mut id = self.root;
+        while self.level(id) > 0 {
+            let half = 1 << (self.level(id) - 1);
+            let index = (line >= half) as usize * 2 + (column >= half) as usize;
+            id = self.children(id)[index];
+            line %= half;
+            column %= half;
+        }
+        id == ALIVE
+    }
+
+    fn population(&self) -> usize {
+        self.node(self.root).population as usize
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn cells(&self) -> Vec<Entity> {
+        // a root wider than the coordinates is clamped to them, cells outside are not listed
+        let half = self.half();
+        let min = i32::try_from(-half).unwrap_or(i32::MIN);
+        let max = i32::try_from(half - 1).unwrap_or(i32::MAX);
+        self.cells_in_rect(&Boundaries {
+            line_min: min,
+            line_max: max,
+            column_min: min,
+            column_max: max,
+        })
+    }
+
+    fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+        self.results.clear();
+    }
+
+    fn boxed_clone(&self) -> Box<dyn LifeEngine> {
+        Box::new(self.clone())
+    }
+
+    fn step_limit(&self) -> u64 {
+        // any number of generations takes a few steps of powers of two
+        u64::MAX
+    }
+
+    fn cells_in_rect(&self, rect: &Boundaries) -> Vec<Entity> {
+        let mut cells = vec![];
+        let half = self.half();
+        let rect = [
+            i64::from(rect.line_min),
+            i64::from(rect.line_max),
+            i64::from(rect.column_min),
+            i64::from(rect.column_max),
+        ];
+        self.collect(self.root, -half, -half, &rect, &mut cells);
+        // the quadtree walk is ordered by quadrant, not by line
+        cells.sort();
+        cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: &str = r#"
+010
+001
+111
+        "#;
+
+    #[test]
+    fn test_set_get_cell() {
+        let mut hashlife = HashLife::new(Rule::default());
+        hashlife.set_cell(0, 0, true);
+        hashlife.set_cell(-100, 3, true);
+        hashlife.set_cell(5, -2000, true);
+        assert_eq!(3, hashlife.population());
+        assert!(hashlife.get_cell(-100, 3));
+        assert!(hashlife.get_cell(5, -2000));
+        assert!(!hashlife.get_cell(5, 2000));
+        hashlife.set_cell(-100, 3, false);
+        assert_eq!(2, hashlife.population());
+        assert_eq!(
+            vec![
+                Entity {
+                    line: 0,
+                    column: 0,
+                    state: 1,
+                    team: 0
+                },
+                Entity {
+                    line: 5,
+                    column: -2000,
+                    state: 1,
+                    team: 0
+                }
+            ],
+            hashlife.cells()
+        );
+    }
+
+    #[test]
+    fn test_glider() {
+        let mut universe = Universe::new(GLIDER);
+        let mut hashlife = HashLife::from_universe(&universe);
+        for _ in 0..100 {
+            universe.step(1);
+            hashlife.step(1);
+            assert_eq!(universe.cells(), hashlife.cells());
+        }
+        let mut jump = HashLife::from_universe(&Universe::new(GLIDER));
+        jump.step(100);
+        assert_eq!(universe, jump.to_universe());
+    }
+
+    #[test]
+    fn test_glider_jump() {
+        let mut hashlife = HashLife::from_universe(&Universe::new(GLIDER));
+        hashlife.step(1 << 20);
+        let mut expected = Universe::new(GLIDER);
+        expected.translate(1 << 18, 1 << 18);
+        assert_eq!(expected, hashlife.to_universe());
+    }
+
+    #[test]
+    fn test_translate() {
+        let mut universe = Universe::new(GLIDER);
+        let mut hashlife = HashLife::from_universe(&universe);
+        for (lines, columns) in [(1, 0), (-7, 300), (100_000, -3)] {
+            hashlife.translate(lines, columns);
+            universe.translate(lines, columns);
+            assert_eq!(universe, hashlife.to_universe());
+        }
+    }
+
+    #[test]
+    fn test_from_macrocell() {
+        // the glider at the center of a root far larger than the coordinates
+        let mut macrocell = "[M2]\n#G 7\n.*$..*$***$\n4 1 0 0 0\n".to_string();
+        for level in 5..=40 {
+            macrocell.push_str(&format!("{} {} 0 0 0\n", level, level - 3));
+        }
+        macrocell.push_str("41 0 0 0 38\n");
+        let far = format!("{}42 38 0 0 39\n", macrocell);
+        let macrocell = Macrocell::parse(&macrocell).unwrap();
+        let hashlife = HashLife::from_macrocell(&macrocell);
+        assert_eq!(7, hashlife.generation());
+        assert_eq!(macrocell.to_universe().unwrap(), hashlife.to_universe());
+        assert_eq!(Universe::new(GLIDER), hashlife.to_universe());
+        assert!(Macrocell::parse(&far).is_err());
+    }
+
+    #[test]
+    fn test_huge_steps() {
+        let block = Universe::new("11\n11");
+        let mut hashlife = HashLife::from_universe(&block);
+        hashlife.step(1 << 62);
+        hashlife.step(u64::MAX);
+        assert_eq!(u64::MAX, hashlife.generation());
+        assert_eq!(block, hashlife.to_universe());
+        // the glider leaves the coordinates, the root grows past them
+        let mut universe = Universe::new(GLIDER);
+        universe.translate(10, 10);
+        universe.set_cell(0, 0, true);
+        universe.set_cell(0, 1, true);
+        universe.set_cell(1, 0, true);
+        universe.set_cell(1, 1, true);
+        let mut hashlife = HashLife::from_universe(&universe);
+        hashlife.step(1 << 40);
+        assert!(hashlife.half() > i64::from(i32::MAX));
+        assert_eq!(9, hashlife.population());
+        assert_eq!(block, hashlife.to_universe());
+    }
+
+    #[test]
+    fn test_collect_garbage() {
+        let mut universe = Universe::new(".11\n11\n.1");
+        let mut hashlife = HashLife::from_universe(&universe);
+        for _ in 0..100 {
+            hashlife.step(1);
+        }
+        let nodes = hashlife.nodes.len();
+        let cells = hashlife.cells();
+        hashlife.collect_garbage();
+        assert!(hashlife.nodes.len() < nodes / 2);
+        assert!(hashlife.results.is_empty());
+        assert_eq!(cells, hashlife.cells());
+        assert_eq!(100, hashlife.generation());
+        universe.step(150);
+        hashlife.step(50);
+        assert_eq!(universe.cells(), hashlife.cells());
+    }
+
+    #[test]
+    fn test_highlife() {
+        let mut universe = Universe::new(
+            r#"
+00111
+01001
+10001
+10010
+11100
+        "#,
+        );
+        universe.rule = Rule::parse("B36/S23").unwrap();
+        let mut hashlife = HashLife::from_universe(&universe);
+        universe.step(48);
+        hashlife.step(48);
+        assert_eq!(universe, hashlife.to_universe());
+    }
+}
@@ -0,0 +1,302 @@
+use crate::engine::LifeEngine;
+use crate::universe::block::BlockRule;
+use crate::universe::rule::Rule;
+use crate::universe::topology::Topology;
+use crate::universe::{Boundaries, Entity, Universe};
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+/// The cells of a block with their weight in the block number: top left, top right, bottom
+/// left and bottom right.
+const BLOCK: [(i32, i32, u8); 4] = [(0, 0, 1), (0, 1, 2), (1, 0, 4), (1, 1, 8)];
+
+/// Engine for [`BlockRule`]s, which update 2x2 blocks of cells at once.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Margolus {
+    universe: Universe,
+    generation: i64,
+    // running the inverse rule, back to the earlier generations
+    reversed: bool,
+}
+
+impl Margolus {
+    pub fn from_universe(universe: &Universe) -> Self {
+        Margolus {
+            universe: universe.clone(),
+            // only the parity matters
+            generation: (universe.generation % 2) as i64,
+            reversed: false,
+        }
+    }
+
+    /// Runs the automaton backwards: the rule is replaced by its inverse, and the next
+    /// generations undo the previous ones.
+    pub fn reverse(&mut self) {
+        if let Some(rule) = self.block_rule() {
+            let inverse = rule.inverse().to_string();
+            let rule = Rule::parse(&format!("{}{}", inverse, self.universe.rule.topology()))
+                .expect("the inverse of a block rule should be valid");
+            self.universe.rule = rule;
+            self.reversed = !self.reversed;
+        }
+    }
+
+    fn block_rule(&self) -> Option<&BlockRule> {
+        self.universe.rule.block()
+    }
+
+    fn complemented(&self) -> bool {
+        self.universe.rule.topology() == &Topology::Plane
+            && self.block_rule().is_some_and(|rule| rule.strobes())
+            && self.generation.rem_euclid(2) == 1
+    }
+
+    fn invert(&mut self) {
+        let Boundaries {
+            line_min,
+            line_max,
+            column_min,
+            column_max,
+        } = match self.universe.bounding_box() {
+            Some(boundaries) => boundaries,
+            None => return,
+        };
+        let parity = self.generation.rem_euclid(2) as i32;
+        let start = |min: i32| (min - parity).div_euclid(2) * 2 + parity;
+        let mut entities = BTreeSet::new();
+        for line in start(line_min)..start(line_max) + 2 {
+            for column in start(column_min)..start(column_max) + 2 {
+                if !self.universe.get_cell(line, column) {
+                    entities.insert(Rc::new(Entity {
+                        line,
+                        column,
+                        state: 1,
+                        team: 0,
+                    }));
+                }
+            }
+        }
+        self.universe.entities = entities;
+    }
+
+    fn tick(&mut self) {
+        let rule = match self.block_rule() {
+            Some(rule) => rule.clone(),
+            None => return,
+        };
+        self.universe.generation += 1;
+        if self.reversed {
+            self.generation -= 1;
+        }
+        let parity = self.generation.rem_euclid(2) as i32;
+        if !self.reversed {
+            self.generation += 1;
+        }
+        let topology = self.universe.rule.topology().clone();
+        let strobes = topology == Topology::Plane && rule.strobes();
+        // undoing a generation that stored the complement uses the other half of the strobe
+        let complement = (parity == 1) == self.reversed;
+        let next = |block: u8| match (strobes, complement) {
+            (false, _) => rule.next(block),
+            (true, true) => !rule.next(block) & 15,
+            (true, false) => rule.next(!block & 15),
+        };
+        let origins: BTreeSet<(i32, i32)> = match topology.size() {
+            None => self
+                .universe
+                .entities
+                .iter()
+                .map(|entity| {
+                    (
+                        (entity.line - parity).div_euclid(2) * 2 + parity,
+                        (entity.column - parity).div_euclid(2) * 2 + parity,
+                    )
+                })
+                .collect(),
+            Some((width, height)) => (0..height / 2)
+                .flat_map(|line| {
+                    (0..width / 2).map(move |column| (line * 2 + parity, column * 2 + parity))
+                })
+                .collect(),
+        };
+        let mut entities = BTreeSet::new();
+        for (line, column) in origins {
+            let cells = BLOCK.iter().filter_map(|(lines, columns, weight)| {
+                topology
+                    .wrap(line + lines, column + columns)
+                    .map(|position| (position, *weight))
+            });
+            let block = cells
+                .clone()
+                .filter(|((line, column), _)| self.universe.state(*line, *column) == 1)
+                .fold(0, |block, (_, weight)| block | weight);
+            let block = next(block);
+            for ((line, column), _) in cells.filter(|(_, weight)| block & weight != 0) {
+                entities.insert(Rc::new(Entity {
+                    line,
+                    column,
+                    state: 1,
+                    team: 0,
+                }));
+            }
+        }
+        self.universe.entities = entities;
+    }
+}
+
+impl LifeEngine for Margolus {
+    fn step(&mut self, generations: u64) {
+        for _ in 0..generations {
+            self.tick();
+        }
+    }
+
+    fn set_cell(&mut self, line: i32, column: i32, alive: bool) {
+        self.universe.set_cell(line, column, alive);
+    }
+
+    fn get_cell(&self, line: i32, column: i32) -> bool {
+        self.universe.get_cell(line, column)
+    }
+
+    fn population(&self) -> usize {
+        self.universe.population()
+    }
+
+    fn generation(&self) -> u64 {
+        self.universe.generation
+    }
+
+    fn cells(&self) -> Vec<Entity> {
+        self.universe.cells()
+    }
+
+    fn rule(&self) -> &Rule {
+        &self.universe.rule
+    }
+
+    fn set_rule(&mut self, rule: Rule) {
+        let complemented = self.complemented();
+        self.universe.set_rule(rule);
+        if complemented != self.complemented() {
+            self.invert();
+        }
+    }
+
+    fn boxed_clone(&self) -> Box<dyn LifeEngine> {
+        Box::new(self.clone())
+    }
+
+    fn bounding_box(&self) -> Option<Boundaries> {
+        self.universe.bounding_box()
+    }
+
+    fn cells_in_rect(&self, rect: &Boundaries) -> Vec<Entity> {
+        self.universe.cells_in_rect(rect)
+    }
+
+    fn to_universe(&self) -> Universe {
+        self.universe.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_billiard_ball() {
+        // a ball moves diagonally by two cells every two generations
+        let mut margolus = Margolus::from_universe(&Universe::new("1"));
+        margolus.set_rule(Rule::parse("BBM").unwrap());
+        margolus.step(1);
+        assert_eq!(vec![(1, 1)], positions(&margolus));
+        margolus.step(1);
+        assert_eq!(vec![(2, 2)], positions(&margolus));
+        margolus.step(2);
+        assert_eq!(vec![(4, 4)], positions(&margolus));
+    }
+
+    fn positions(margolus: &Margolus) -> Vec<(i32, i32)> {
+        margolus
+            .cells()
+            .iter()
+            .map(|entity| (entity.line, entity.column))
+            .collect()
+    }
+
+    #[test]
+    fn test_reversibility() {
+        for rule in ["BBM", "Critters", "Tron", "Critters:T16,16", "Tron:T12,14"] {
+            for generations in [1, 2, 7, 30] {
+                let mut initial = Universe::new("0110\n1101\n0011\n1000");
+                initial.set_rule(Rule::parse(rule).unwrap());
+                let mut margolus = Margolus::from_universe(&initial);
+                margolus.step(generations);
+                let later = margolus.to_universe();
+                assert_ne!(initial, later, "rule {}", rule);
+                margolus.reverse();
+                margolus.step(generations);
+                assert_eq!(initial.cells(), margolus.cells(), "rule {}", rule);
+                // and forward again
+                margolus.reverse();
+                margolus.step(generations);
+                assert_eq!(later, margolus.to_universe(), "rule {}", rule);
+            }
+        }
+    }
+
+    #[test]
+    fn test_odd_generation() {
+        let mut universe = Universe::new(
+            r#"
+0110
+1001
+0011
+        "#,
+        );
+        universe.set_rule(Rule::parse("Critters").unwrap());
+        let mut margolus = Margolus::from_universe(&universe);
+        margolus.step(3);
+        // the block phase and the complement survive a conversion
+        let mut copy = Margolus::from_universe(&margolus.to_universe());
+        margolus.step(5);
+        copy.step(5);
+        assert_eq!(8, copy.generation());
+        assert_eq!(margolus.to_universe(), copy.to_universe());
+
+        // an odd generation lists the dead cells, which are live ones for other rules
+        margolus.step(1);
+        let holes = margolus.cells();
+        let boundaries = margolus.bounding_box().unwrap();
+        margolus.set_rule(Rule::parse("BBM").unwrap());
+        assert!(holes
+            .iter()
+            .all(|entity| !margolus.get_cell(entity.line, entity.column)));
+        let area = (boundaries.line_max - boundaries.line_min + 1)
+            * (boundaries.column_max - boundaries.column_min + 1);
+        assert!(margolus.population() + holes.len() >= area as usize);
+        margolus.set_rule(Rule::parse("Critters").unwrap());
+        assert_eq!(holes, margolus.cells());
+    }
+
+    #[test]
+    fn test_critters_strobe() {
+        // the complement is stored every other generation on the plane, so the pattern matches
+        // the same pattern on a torus large enough not to wrap around
+        let mut universe = Universe::new("0110\n1101\n0011\n1000");
+        universe.set_rule(Rule::parse("Critters").unwrap());
+        let mut plane = Margolus::from_universe(&universe);
+        let mut torus = universe.clone();
+        torus.set_rule(Rule::parse("Critters:T40,40").unwrap());
+        torus.translate(18, 18);
+        let mut torus = Margolus::from_universe(&torus);
+        for _ in 0..4 {
+            plane.step(2);
+            torus.step(2);
+            let mut expected = plane.to_universe();
+            expected.translate(18, 18);
+            assert_eq!(expected.cells(), torus.cells());
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod components;
+pub mod engine;
+pub mod simulation;
+pub mod statistics;
+pub mod universe;
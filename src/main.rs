@@ -1,6 +1,3 @@
-mod components;
-mod universe;
-
 fn main() {
-    yew::Renderer::<components::app::App>::new().render();
+    yew::Renderer::<yewlife::components::app::App>::new().render();
 }
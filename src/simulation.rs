@@ -0,0 +1,257 @@
+use crate::engine::hashlife::HashLife;
+use crate::engine::{EngineKind, LifeEngine};
+use crate::statistics::History;
+use crate::universe::period::PeriodDetector;
+use crate::universe::rule::Rule;
+use crate::universe::soup::Soup;
+use crate::universe::Universe;
+use std::cell::{Ref, RefCell, RefMut};
+use std::rc::Rc;
+
+/// The state shared by the view components: the active [`LifeEngine`] and its kind.
+#[derive(Clone)]
+pub struct Simulation {
+    pub kind: EngineKind,
+    /// The kind chosen by the user, used again as soon as the rule allows it.
+    pub preferred: EngineKind,
+    engine: Rc<RefCell<Box<dyn LifeEngine>>>,
+    /// Settings of the last random pattern, so it can be shared and generated again.
+    pub soup: Soup,
+    /// Population of the last generations.
+    pub history: History,
+    /// Whether the cells repeat, only followed while running one generation at a time.
+    pub period: PeriodDetector,
+    // bumped on every change, so consumers do not have to compare whole engines
+    revision: u64,
+}
+
+impl PartialEq for Simulation {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.revision == other.revision
+    }
+}
+
+impl Simulation {
+    pub fn new(preferred: EngineKind, universe: &Universe) -> Self {
+        let kind = choose(preferred, &universe.rule);
+        let engine = kind.create(universe);
+        Simulation {
+            kind,
+            preferred,
+            history: History::new(engine.as_ref()),
+            engine: Rc::new(RefCell::new(engine)),
+            period: PeriodDetector::default(),
+            soup: Soup::default(),
+            revision: 0,
+        }
+    }
+
+    pub fn engine(&self) -> Ref<'_, dyn LifeEngine> {
+        Ref::map(self.engine.borrow(), |engine| engine.as_ref())
+    }
+
+    pub fn engine_mut(&mut self) -> RefMut<'_, dyn LifeEngine> {
+        RefMut::map(self.engine.borrow_mut(), |engine| engine.as_mut())
+    }
+
+    /// Replaces the engine content, keeping the engine kind if it supports the new rule, and
+    /// starts a new history.
+    pub fn load(&mut self, universe: &Universe) {
+        self.replace(universe);
+        self.reset_history();
+    }
+
+    /// Runs HashLife on the tree, which may hold far more cells than could be listed.
+    pub fn load_hashlife(&mut self, hashlife: HashLife) {
+        self.kind = EngineKind::HashLife;
+        *self.engine.borrow_mut() = Box::new(hashlife);
+        self.reset_history();
+    }
+
+    /// Starts the history again from the current generation, after the cells were changed
+    /// outside of the simulation steps.
+    pub fn reset_history(&mut self) {
+        let history = History::new(&*self.engine());
+        self.history = history;
+        self.restart_period();
+    }
+
+    /// Looks for a period from the current generation on, after the cells or the rule were
+    /// changed.
+    pub fn restart_period(&mut self) {
+        self.period.reset();
+        self.period.observe(self.engine.borrow().as_ref());
+    }
+
+    fn replace(&mut self, universe: &Universe) {
+        self.kind = choose(self.preferred, &universe.rule);
+        *self.engine.borrow_mut() = self.kind.create(universe);
+    }
+
+    /// Generations a single step runs at most, HashLife taking over the longer jumps when the
+    /// rule allows it.
+    pub fn step_limit(&self) -> u64 {
+        let engine = self.engine();
+        if EngineKind::HashLife.supports(engine.rule()) {
+            u64::MAX
+        } else {
+            engine.step_limit()
+        }
+    }
+
+    /// Advances the engine by the given number of generations at once, and records them.
+    pub fn step(&mut self, generations: u64) {
+        if generations > self.engine().step_limit() {
+            self.switch(EngineKind::HashLife);
+        }
+        self.history
+            .step(self.engine.borrow_mut().as_mut(), generations);
+        // generations skipped by a jump would hide the shortest period, and the cells after a
+        // jump are only listed once the next generation is run
+        if generations == 1 {
+            self.period.observe(self.engine.borrow().as_ref());
+        } else {
+            self.period.reset();
+        }
+    }
+
+    /// Switches to another engine kind, keeping the cells and the rule, or as soon as the rule
+    /// allows it.
+    pub fn set_kind(&mut self, kind: EngineKind) {
+        self.preferred = kind;
+        self.switch(kind);
+    }
+
+    fn switch(&mut self, kind: EngineKind) {
+        if kind == self.kind || !kind.supports(self.engine().rule()) {
+            return;
+        }
+        let universe = self.engine().to_universe();
+        *self.engine.borrow_mut() = kind.create(&universe);
+        self.kind = kind;
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        if choose(self.preferred, &rule) == self.kind {
+            self.engine_mut().set_rule(rule);
+        } else {
+            let mut universe = self.engine().to_universe();
+            universe.set_rule(rule);
+            self.replace(&universe);
+        }
+        self.restart_period();
+    }
+
+    pub fn touch(&mut self) {
+        self.revision += 1;
+    }
+}
+
+fn choose(preferred: EngineKind, rule: &Rule) -> EngineKind {
+    if preferred.supports(rule) {
+        preferred
+    } else {
+        EngineKind::fallback(rule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::universe::period::Behavior;
+
+    #[test]
+    fn test_set_kind() {
+        let glider = Universe::new(
+            r#"
+010
+001
+111
+        "#,
+        );
+        let mut simulation = Simulation::new(EngineKind::Universe, &glider);
+        let previous = simulation.kind;
+        simulation.engine_mut().step(4);
+        simulation.set_kind(EngineKind::HashLife);
+        assert_ne!(previous, simulation.kind);
+        simulation.engine_mut().step(4);
+        simulation.set_kind(EngineKind::Dense);
+        simulation.engine_mut().step(4);
+        // the generation goes on with the new engines
+        assert_eq!(12, simulation.engine().generation());
+        let mut expected = glider;
+        expected.translate(3, 3);
+        assert_eq!(expected, simulation.engine().to_universe());
+    }
+
+    #[test]
+    fn test_clone_shares_engine() {
+        let mut simulation = Simulation::new(EngineKind::HashLife, &Universe::new("111"));
+        let copy = simulation.clone();
+        simulation.step(5);
+        assert_eq!(5, copy.engine().generation());
+        // the reducer compares the revisions, not the engines
+        simulation.touch();
+        assert!(copy != simulation);
+    }
+
+    #[test]
+    fn test_unsupported_rule() {
+        let mut simulation = Simulation::new(EngineKind::HashLife, &Universe::new("111"));
+        simulation.set_rule(Rule::parse("B3/S23:T10,10").unwrap());
+        assert_eq!(EngineKind::Universe, simulation.kind);
+        simulation.set_kind(EngineKind::Dense);
+        assert_eq!(EngineKind::Universe, simulation.kind);
+        // back to the chosen engine once the rule allows it
+        simulation.set_rule(Rule::default());
+        assert_eq!(EngineKind::Dense, simulation.kind);
+        assert_eq!(3, simulation.engine().population());
+
+        simulation.set_rule(Rule::parse("Critters").unwrap());
+        assert_eq!(EngineKind::Margolus, simulation.kind);
+        simulation.set_kind(EngineKind::Universe);
+        assert_eq!(EngineKind::Margolus, simulation.kind);
+        simulation.set_rule(Rule::default());
+        assert_eq!(EngineKind::Universe, simulation.kind);
+        assert_eq!(3, simulation.engine().population());
+    }
+
+    #[test]
+    fn test_step() {
+        let mut simulation = Simulation::new(EngineKind::HashLife, &Universe::new("111"));
+        simulation.step(1000);
+        simulation.step(3);
+        assert_eq!(1003, simulation.engine().generation());
+        assert_eq!(3, simulation.history.records().count());
+        let mut blinker = simulation.engine().to_universe();
+        blinker.translate(1, 0);
+        assert_eq!(Universe::new("010\n010\n010"), blinker);
+        // changing the rule keeps counting, loading cells starts again
+        simulation.set_rule(Rule::parse("B3/S23:T10,10").unwrap());
+        simulation.step(1);
+        assert_eq!(1004, simulation.engine().generation());
+        assert_eq!(4, simulation.history.records().count());
+        simulation.load(&Universe::new("1"));
+        assert_eq!(0, simulation.engine().generation());
+        assert_eq!(1, simulation.history.records().count());
+        simulation.step(1);
+        simulation.step(1);
+        assert_eq!(Some(Behavior::Empty), simulation.period.behavior());
+        simulation.load(&Universe::new("111"));
+        simulation.step(1);
+        assert_eq!(None, simulation.period.behavior());
+        simulation.step(1);
+        assert_eq!(
+            Some(Behavior::Oscillator { period: 2 }),
+            simulation.period.behavior()
+        );
+        simulation.step(10);
+        assert_eq!(None, simulation.period.behavior());
+        // the cells are followed again from the generation after the jump
+        simulation.step(1);
+        simulation.step(1);
+        assert_eq!(None, simulation.period.behavior());
+        simulation.step(1);
+        assert!(simulation.period.behavior().is_some());
+    }
+}
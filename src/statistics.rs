@@ -0,0 +1,173 @@
+use crate::engine::LifeEngine;
+use std::collections::VecDeque;
+
+/// Number of records kept by a [`History`]; older ones are dropped.
+pub const HISTORY_SIZE: usize = 4096;
+
+/// The population of a generation, and the cells born and dead since the previous record,
+/// when they are known.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct Record {
+    pub generation: u64,
+    pub population: usize,
+    pub births: Option<usize>,
+    pub deaths: Option<usize>,
+}
+
+/// Population of the last generations, one record for each step of the simulation.
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct History {
+    records: VecDeque<Record>,
+}
+
+impl History {
+    /// A history starting with the current generation of the engine.
+    pub fn new(engine: &dyn LifeEngine) -> Self {
+        let mut history = History::default();
+        history.push(Record {
+            generation: engine.generation(),
+            population: engine.population(),
+            births: Some(0),
+            deaths: Some(0),
+        });
+        history
+    }
+
+    fn push(&mut self, record: Record) {
+        if self.records.len() == HISTORY_SIZE {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    /// Advances the engine by the given number of generations, and records the result.
+    pub fn step(&mut self, engine: &mut dyn LifeEngine, generations: u64) {
+        let changes = if generations == 1 {
+            engine.step_counted()
+        } else {
+            engine.step(generations);
+            None
+        };
+        self.push(Record {
+            generation: engine.generation(),
+            population: engine.population(),
+            births: changes.map(|(births, _)| births),
+            deaths: changes.map(|(_, deaths)| deaths),
+        });
+    }
+
+    /// The records from the oldest to the latest.
+    pub fn records(&self) -> impl Iterator<Item = &Record> {
+        self.records.iter()
+    }
+
+    pub fn latest(&self) -> Option<&Record> {
+        self.records.back()
+    }
+
+    /// The records from the given generation on.
+    pub fn since(&self, generation: u64) -> impl Iterator<Item = &Record> {
+        self.records
+            .iter()
+            .filter(move |record| record.generation >= generation)
+    }
+
+    /// The records as comma separated values, with a header line.
+    pub fn to_csv(&self) -> String {
+        // unknown values are left empty
+        let value = |value: Option<usize>| value.map_or(String::new(), |value| value.to_string());
+        let mut csv = String::from("generation,population,births,deaths\n");
+        for record in &self.records {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                record.generation,
+                record.population,
+                value(record.births),
+                value(record.deaths)
+            ));
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::EngineKind;
+    use crate::universe::Universe;
+
+    #[test]
+    fn test_history() {
+        let mut blinker = Universe::new("111");
+        let mut history = History::new(&blinker);
+        history.step(&mut blinker, 1);
+        history.step(&mut blinker, 2);
+        assert_eq!(
+            vec![
+                Record {
+                    generation: 0,
+                    population: 3,
+                    births: Some(0),
+                    deaths: Some(0)
+                },
+                Record {
+                    generation: 1,
+                    population: 3,
+                    births: Some(2),
+                    deaths: Some(2)
+                },
+                Record {
+                    generation: 3,
+                    population: 3,
+                    births: None,
+                    deaths: None
+                },
+            ],
+            history.records().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "generation,population,births,deaths\n0,3,0,0\n1,3,2,2\n3,3,,\n",
+            history.to_csv()
+        );
+        assert_eq!(2, history.since(1).count());
+        for _ in 0..HISTORY_SIZE {
+            history.step(&mut blinker, 2);
+        }
+        assert_eq!(HISTORY_SIZE, history.records().count());
+        assert_eq!(
+            Some(3 + 2 * HISTORY_SIZE as u64),
+            history.latest().map(|record| record.generation)
+        );
+        assert_eq!(
+            Some(5),
+            history.records().next().map(|record| record.generation)
+        );
+    }
+
+    #[test]
+    fn test_engine_counts() {
+        let mut history = History::default();
+        for kind in [EngineKind::Universe, EngineKind::Dense] {
+            let mut engine = kind.create(&Universe::new("111\n100"));
+            history.step(engine.as_mut(), 1);
+            assert_eq!(
+                Some((Some(1), Some(1))),
+                history
+                    .latest()
+                    .map(|record| (record.births, record.deaths))
+            );
+        }
+        // HashLife would have to list its cells
+        let mut hashlife = EngineKind::HashLife.create(&Universe::new("111"));
+        history.step(hashlife.as_mut(), 1);
+        assert_eq!(
+            Some(&Record {
+                generation: 1,
+                population: 3,
+                births: None,
+                deaths: None
+            }),
+            history.latest()
+        );
+    }
+}
@@ -1,16 +1,43 @@
 use crate::components::Dimensions;
+use crate::engine::LifeEngine;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use std::cmp::Ordering;
 use std::collections::BTreeSet;
 use std::fmt::{Debug, Display, Formatter, Result};
 use std::rc::Rc;
 use yew::prelude::*;
 
+pub mod apgcode;
+pub mod block;
+pub mod census;
+pub mod elementary;
+pub mod format;
+pub mod hensel;
+pub mod life;
+pub mod ltl;
+pub mod macrocell;
+pub mod neighborhood;
+pub mod period;
+pub mod plaintext;
+pub mod rle;
+pub mod rule;
+pub mod soup;
+pub mod stochastic;
+pub mod table;
+pub mod topology;
+
+use rule::Rule;
+
 pub const CELL_SIZE: i32 = 20;
 
-#[derive(Eq, PartialEq, Debug, Clone, Properties)]
+/// A cell, identified by its position.
+#[derive(Debug, Clone, Properties)]
 pub struct Entity {
     pub line: i32,
     pub column: i32,
+    pub state: u8,
+    pub team: u8,
 }
 
 impl Entity {
@@ -40,46 +67,109 @@ impl PartialOrd for Entity {
     }
 }
 
-#[derive(Eq, PartialEq, Debug, Clone, Properties)]
+impl PartialEq for Entity {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entity {}
+
+/// Live cells and the rule they follow.
+#[derive(Debug, Clone, Properties)]
 pub struct Universe {
     pub entities: BTreeSet<Rc<Entity>>,
+    pub rule: Rule,
+    /// Generations run since the universe was created; not part of the equality.
+    pub generation: u64,
+    random: Box<StdRng>,
 }
 
+impl PartialEq for Universe {
+    fn eq(&self, other: &Self) -> bool {
+        self.rule == other.rule
+            && self.entities.len() == other.entities.len()
+            && self
+                .entities
+                .iter()
+                .zip(other.entities.iter())
+                .all(|(a, b)| a == b && a.state == b.state && a.team == b.team)
+    }
+}
+
+impl Eq for Universe {}
+
 impl Display for Universe {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         write!(
             f,
-            "Universe {:?}",
+            "Universe {} {:?}",
+            self.rule,
             self.entities.iter().cloned().collect::<Vec<Rc<Entity>>>()
         )
     }
 }
 
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct ParseError {
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(message: String) -> Self {
+        ParseError { message }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.message)
+    }
+}
+
 #[derive(Eq, PartialEq, Debug, Clone, Properties)]
-struct Boundaries {
-    line_max: i32,
-    line_min: i32,
-    column_max: i32,
-    column_min: i32,
+pub struct Boundaries {
+    pub line_max: i32,
+    pub line_min: i32,
+    pub column_max: i32,
+    pub column_min: i32,
+}
+
+impl Boundaries {
+    pub fn contains(&self, line: i32, column: i32) -> bool {
+        (self.line_min..=self.line_max).contains(&line)
+            && (self.column_min..=self.column_max).contains(&column)
+    }
 }
 
 impl Universe {
+    /// Creates a universe from a matrix of cell states, `0` for empty and `1` for live cells.
     pub fn new(matrix: &str) -> Self {
         let mut universe = BTreeSet::new();
         for (line, line_value) in matrix.trim().lines().enumerate() {
             for (column, entry) in line_value.chars().enumerate() {
-                if entry == '1' {
-                    universe.insert(Rc::new(Entity {
-                        line: line as i32,
-                        column: column as i32,
-                    }));
+                match entry.to_digit(10) {
+                    Some(state) if state > 0 => {
+                        universe.insert(Rc::new(Entity {
+                            line: line as i32,
+                            column: column as i32,
+                            state: state as u8,
+                            team: 0,
+                        }));
+                    }
+                    _ => {}
                 }
             }
         }
-        Universe { entities: universe }
+        Universe {
+            entities: universe,
+            rule: Rule::default(),
+            generation: 0,
+            random: Box::new(StdRng::seed_from_u64(0)),
+        }
     }
 
-    fn boundaries(&self) -> Boundaries {
+    pub fn boundaries(&self) -> Boundaries {
         let lines: BTreeSet<i32> = self.entities.iter().map(|cell| cell.line).collect();
         let line_max = *lines.iter().max().expect("empty collection?");
         let line_min = *lines.iter().min().expect("empty collection?");
@@ -94,73 +184,260 @@ impl Universe {
         }
     }
 
-    fn number_of_neighbors(&mut self, line: i32, column: i32) -> usize {
-        [
-            self.entities.get(&Entity {
-                line: line - 1,
-                column: column - 1,
-            }), // top_left
-            self.entities.get(&Entity {
-                line: line - 1,
-                column,
-            }), // top
-            self.entities.get(&Entity {
-                line: line - 1,
-                column: column + 1,
-            }), // top_right
-            self.entities.get(&Entity {
-                line: line + 1,
-                column: column - 1,
-            }), // bottom_left
-            self.entities.get(&Entity {
-                line: line + 1,
-                column,
-            }), // bottom
-            self.entities.get(&Entity {
-                line: line + 1,
-                column: column + 1,
-            }), // bottom_right
-            self.entities.get(&Entity {
-                line,
-                column: column - 1,
-            }), // left
-            self.entities.get(&Entity {
-                line,
-                column: column + 1,
-            }), // right
-        ]
-        .iter()
-        .map(|c| c.is_some())
-        .filter(|c| *c)
-        .count()
+    pub fn translate(&mut self, lines: i32, columns: i32) {
+        self.entities = self
+            .entities
+            .iter()
+            .map(|entity| {
+                Rc::new(Entity {
+                    line: entity.line + lines,
+                    column: entity.column + columns,
+                    state: entity.state,
+                    team: entity.team,
+                })
+            })
+            .collect();
+    }
+
+    fn entity(&self, line: i32, column: i32) -> Option<&Rc<Entity>> {
+        self.entities.get(&Entity {
+            line,
+            column,
+            state: 0,
+            team: 0,
+        })
+    }
+
+    pub fn state(&self, line: i32, column: i32) -> u8 {
+        self.entity(line, column).map_or(0, |entity| entity.state)
+    }
+
+    fn configuration(&self, line: i32, column: i32) -> u8 {
+        let topology = self.rule.topology();
+        self.rule
+            .neighborhood()
+            .offsets()
+            .filter_map(|(bit, (lines, columns))| {
+                topology
+                    .wrap(line + lines, column + columns)
+                    .map(|position| (bit, position))
+            })
+            .filter(|(_, (line, column))| self.state(*line, *column) == 1)
+            .fold(0, |configuration, (bit, _)| configuration | bit)
+    }
+
+    fn parent_teams(&self, line: i32, column: i32) -> Vec<u8> {
+        let topology = self.rule.topology();
+        self.rule
+            .neighborhood()
+            .offsets()
+            .filter_map(|(_, (lines, columns))| topology.wrap(line + lines, column + columns))
+            .filter_map(|(line, column)| self.entity(line, column))
+            .filter(|entity| entity.state == 1)
+            .map(|entity| entity.team)
+            .collect()
+    }
+
+    fn neighbor_states(&self, line: i32, column: i32, offsets: &[(i32, i32)]) -> Vec<u8> {
+        let topology = self.rule.topology();
+        offsets
+            .iter()
+            .map(|(lines, columns)| {
+                topology
+                    .wrap(line + lines, column + columns)
+                    .map_or(0, |(line, column)| self.state(line, column))
+            })
+            .collect()
     }
 
     pub fn tick(&mut self) {
-        if self.entities.is_empty() {
-            return;
+        self.generation += 1;
+        if let Some(number) = self.rule.elementary() {
+            return self.add_row(number);
         }
-        let Boundaries {
-            line_max: x_max,
-            line_min: x_min,
-            column_max: y_max,
-            column_min: y_min,
-        } = self.boundaries();
+        let area = match self.rule.topology().size() {
+            // finite grids are fully scanned, as B0 rules can create cells anywhere
+            Some((width, height)) => Boundaries {
+                line_min: 0,
+                line_max: height - 1,
+                column_min: 0,
+                column_max: width - 1,
+            },
+            None => {
+                if self.entities.is_empty() {
+                    return;
+                }
+                let radius = self.rule.radius();
+                let boundaries = self.boundaries();
+                Boundaries {
+                    line_min: boundaries.line_min - radius,
+                    line_max: boundaries.line_max + radius,
+                    column_min: boundaries.column_min - radius,
+                    column_max: boundaries.column_max + radius,
+                }
+            }
+        };
+        let counts = self
+            .rule
+            .larger_than_life()
+            .map(|rule| rule.counts(self, &area));
         let mut new_entities = BTreeSet::new();
-        for line in (x_min - 1)..(x_max + 2) {
-            for column in (y_min - 1)..(y_max + 2) {
-                let neighbors = self.number_of_neighbors(line, column);
-                let this_cell = Rc::new(Entity { line, column });
-                let exist = self.entities.contains(&this_cell);
-                if !exist {
-                    if neighbors == 3 {
-                        new_entities.insert(this_cell);
-                    }
-                } else if neighbors == 3 || neighbors == 2 {
-                    new_entities.insert(this_cell);
+        let mut random = self.random.clone();
+        let positions = (area.line_min..=area.line_max)
+            .flat_map(|line| (area.column_min..=area.column_max).map(move |column| (line, column)));
+        for (index, (line, column)) in positions.enumerate() {
+            let previous = self.state(line, column);
+            let state = match (&counts, self.rule.table()) {
+                (Some(counts), _) => self.rule.next_state_from_count(previous, counts[index]),
+                (None, Some(table)) => table.next_state(
+                    previous,
+                    &self.neighbor_states(line, column, table.offsets()),
+                ),
+                (None, None) => {
+                    let configuration = self.configuration(line, column);
+                    self.rule
+                        .next_state_with(previous, configuration, &mut random)
                 }
+            };
+            if state != 0 {
+                // survivors keep their team, newborns take the one of most of their parents
+                let team = match self.entity(line, column) {
+                    _ if self.rule.teams() == 1 => 0,
+                    Some(entity) if entity.state == 1 => entity.team,
+                    _ => self.rule.newborn_team(&self.parent_teams(line, column)),
+                };
+                new_entities.insert(Rc::new(Entity {
+                    line,
+                    column,
+                    state,
+                    team,
+                }));
             }
         }
         self.entities = new_entities;
+        self.random = random;
+    }
+}
+
+impl LifeEngine for Universe {
+    fn step(&mut self, generations: u64) {
+        for _ in 0..generations {
+            self.tick();
+        }
+    }
+
+    fn set_cell(&mut self, line: i32, column: i32, alive: bool) {
+        self.set_state(line, column, alive as u8);
+    }
+
+    fn get_cell(&self, line: i32, column: i32) -> bool {
+        self.state(line, column) != 0
+    }
+
+    fn set_state(&mut self, line: i32, column: i32, state: u8) {
+        let entity = Rc::new(Entity {
+            line,
+            column,
+            state,
+            team: 0,
+        });
+        if state != 0 {
+            self.entities.replace(entity);
+        } else {
+            self.entities.remove(&entity);
+        }
+    }
+
+    fn get_state(&self, line: i32, column: i32) -> u8 {
+        self.state(line, column)
+    }
+
+    fn seed(&mut self, seed: u64) {
+        *self.random = StdRng::seed_from_u64(seed);
+    }
+
+    fn set_team(&mut self, line: i32, column: i32, team: u8) {
+        self.entities.replace(Rc::new(Entity {
+            line,
+            column,
+            state: 1,
+            team,
+        }));
+    }
+
+    fn population(&self) -> usize {
+        self.entities.len()
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn cells(&self) -> Vec<Entity> {
+        self.entities
+            .iter()
+            .map(|entity| (**entity).clone())
+            .collect()
+    }
+
+    fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn set_rule(&mut self, rule: Rule) {
+        if rule.teams() != self.rule.teams() {
+            // keeps the teams within the colors of the new rule
+            self.entities = self
+                .entities
+                .iter()
+                .map(|entity| {
+                    Rc::new(Entity {
+                        team: entity.team % rule.teams(),
+                        ..**entity
+                    })
+                })
+                .collect();
+        }
+        self.rule = rule;
+    }
+
+    fn boxed_clone(&self) -> Box<dyn LifeEngine> {
+        Box::new(self.clone())
+    }
+
+    fn bounding_box(&self) -> Option<Boundaries> {
+        if self.entities.is_empty() {
+            None
+        } else {
+            Some(self.boundaries())
+        }
+    }
+
+    fn cells_in_rect(&self, rect: &Boundaries) -> Vec<Entity> {
+        let first = Entity {
+            line: rect.line_min,
+            column: rect.column_min,
+            state: 1,
+            team: 0,
+        };
+        let last = Entity {
+            line: rect.line_max,
+            column: rect.column_max,
+            state: 1,
+            team: 0,
+        };
+        if first > last {
+            return vec![];
+        }
+        self.entities
+            .range(first..=last)
+            .filter(|entity| rect.contains(entity.line, entity.column))
+            .map(|entity| (**entity).clone())
+            .collect()
+    }
+
+    fn to_universe(&self) -> Universe {
+        self.clone()
     }
 }
 
@@ -228,53 +505,188 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_topologies() {
+        let glider = r#"
+010
+001
+111
+        "#;
+        let expected = [
+            // the glider turns into a block in the corner
+            ("B3/S23:P8,8", vec![(6, 6), (6, 7), (7, 6), (7, 7)]),
+            ("B3/S23:T8,8", vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]),
+            // crossing the twisted edge mirrors the glider
+            ("B3/S23:K8*,8", vec![(0, 6), (1, 5), (2, 5), (2, 6), (2, 7)]),
+            ("B3/S23:C8,8", vec![(0, 0), (7, 7)]),
+        ];
+        for (rule, cells) in expected {
+            let mut universe = Universe::new(glider);
+            universe.rule = Rule::parse(rule).unwrap();
+            for _ in 0..32 {
+                universe.tick();
+            }
+            assert_eq!(
+                cells,
+                universe
+                    .entities
+                    .iter()
+                    .map(|e| (e.line, e.column))
+                    .collect::<Vec<_>>(),
+                "rule {}",
+                rule
+            );
+        }
+    }
+
     #[test]
     fn test_universe_order() {
         let entities = BTreeSet::from_iter(
             [
-                Rc::new(Entity { line: 1, column: 0 }),
-                Rc::new(Entity { line: 0, column: 0 }),
-                Rc::new(Entity { line: 0, column: 0 }),
-                Rc::new(Entity { line: 0, column: 1 }),
+                Rc::new(Entity {
+                    line: 1,
+                    column: 0,
+                    state: 1,
+                    team: 0,
+                }),
+                Rc::new(Entity {
+                    line: 0,
+                    column: 0,
+                    state: 1,
+                    team: 0,
+                }),
+                Rc::new(Entity {
+                    line: 0,
+                    column: 0,
+                    state: 1,
+                    team: 0,
+                }),
+                Rc::new(Entity {
+                    line: 0,
+                    column: 1,
+                    state: 1,
+                    team: 0,
+                }),
                 Rc::new(Entity {
                     line: 0,
                     column: -1,
+                    state: 1,
+                    team: 0,
                 }),
             ]
             .iter()
             .cloned(),
         );
-        let universe = Universe { entities };
+        let universe = Universe {
+            entities,
+            ..Universe::new("")
+        };
         let mut iter = universe.entities.iter();
         assert_eq!(
             Some(&Rc::new(Entity {
                 line: 0,
-                column: -1
+                column: -1,
+                state: 1,
+                team: 0
+            })),
+            iter.next()
+        );
+        assert_eq!(
+            Some(&Rc::new(Entity {
+                line: 0,
+                column: 0,
+                state: 1,
+                team: 0
+            })),
+            iter.next()
+        );
+        assert_eq!(
+            Some(&Rc::new(Entity {
+                line: 0,
+                column: 1,
+                state: 1,
+                team: 0
+            })),
+            iter.next()
+        );
+        assert_eq!(
+            Some(&Rc::new(Entity {
+                line: 1,
+                column: 0,
+                state: 1,
+                team: 0
             })),
             iter.next()
         );
-        assert_eq!(Some(&Rc::new(Entity { line: 0, column: 0 })), iter.next());
-        assert_eq!(Some(&Rc::new(Entity { line: 0, column: 1 })), iter.next());
-        assert_eq!(Some(&Rc::new(Entity { line: 1, column: 0 })), iter.next());
         assert_eq!(None, iter.next());
     }
 
     #[test]
     fn test_cmp() {
-        let cell_a = Entity { line: 0, column: 0 };
-        let cell_b = Entity { line: 0, column: 0 };
+        let cell_a = Entity {
+            line: 0,
+            column: 0,
+            state: 1,
+            team: 0,
+        };
+        let cell_b = Entity {
+            line: 0,
+            column: 0,
+            state: 1,
+            team: 0,
+        };
         assert_eq!(Ordering::Equal, cell_a.cmp(&cell_b));
-        let cell_a = Entity { line: 0, column: 0 };
-        let cell_b = Entity { line: 1, column: 0 };
+        let cell_a = Entity {
+            line: 0,
+            column: 0,
+            state: 1,
+            team: 0,
+        };
+        let cell_b = Entity {
+            line: 1,
+            column: 0,
+            state: 1,
+            team: 0,
+        };
         assert_eq!(Ordering::Less, cell_a.cmp(&cell_b));
-        let cell_a = Entity { line: 1, column: 0 };
-        let cell_b = Entity { line: 0, column: 0 };
+        let cell_a = Entity {
+            line: 1,
+            column: 0,
+            state: 1,
+            team: 0,
+        };
+        let cell_b = Entity {
+            line: 0,
+            column: 0,
+            state: 1,
+            team: 0,
+        };
         assert_eq!(Ordering::Greater, cell_a.cmp(&cell_b));
-        let cell_a = Entity { line: 0, column: 0 };
-        let cell_b = Entity { line: 0, column: 1 };
+        let cell_a = Entity {
+            line: 0,
+            column: 0,
+            state: 1,
+            team: 0,
+        };
+        let cell_b = Entity {
+            line: 0,
+            column: 1,
+            state: 1,
+            team: 0,
+        };
         assert_eq!(Ordering::Less, cell_a.cmp(&cell_b));
-        let cell_a = Entity { line: 0, column: 1 };
-        let cell_b = Entity { line: 0, column: 0 };
+        let cell_a = Entity {
+            line: 0,
+            column: 1,
+            state: 1,
+            team: 0,
+        };
+        let cell_b = Entity {
+            line: 0,
+            column: 0,
+            state: 1,
+            team: 0,
+        };
         assert_eq!(Ordering::Greater, cell_a.cmp(&cell_b));
     }
 }
@@ -0,0 +1,407 @@
+use crate::engine::LifeEngine;
+use crate::universe::neighborhood::Neighborhood;
+use crate::universe::period::{Behavior, PeriodDetector};
+use crate::universe::rule::Rule;
+use crate::universe::topology::Topology;
+use crate::universe::{Entity, ParseError, Universe};
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+/// Digits of the extended Wechsler format, one for each column of a 5 cells high strip.
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Height of the strips of the extended Wechsler format.
+const STRIP: i32 = 5;
+
+/// Generations run to find the period of an object.
+pub const CLASSIFY_LIMIT: usize = 512;
+
+/// Code of the objects which do not repeat within [`CLASSIFY_LIMIT`] generations, or which
+/// die out on their own.
+pub const UNKNOWN: &str = "zz_UNKNOWN";
+
+/// Common names of some objects.
+const NAMES: [(&str, &str); 14] = [
+    ("xs4_33", "block"),
+    ("xs4_252", "tub"),
+    ("xs5_253", "boat"),
+    ("xs6_356", "ship"),
+    ("xs6_696", "beehive"),
+    ("xs7_2596", "loaf"),
+    ("xs8_6996", "pond"),
+    ("xp2_7", "blinker"),
+    ("xp2_7e", "toad"),
+    ("xp2_318c", "beacon"),
+    ("xp15_4r4z4r4", "pentadecathlon"),
+    ("xq4_153", "glider"),
+    ("xq4_6frc", "lightweight spaceship"),
+    ("xq4_27dee6", "middleweight spaceship"),
+];
+
+/// The common name of the object with the apgcode, for the best known ones.
+pub fn name(code: &str) -> Option<&'static str> {
+    NAMES
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, name)| *name)
+}
+
+/// Whether the value looks like an apgcode of a still life, an oscillator or a spaceship,
+/// such as `xs4_33`, `xp2_7` or `xq4_153`.
+pub fn matches(value: &str) -> bool {
+    let value = value.trim();
+    let prefix = value.get(..2);
+    let number = value
+        .get(2..)
+        .and_then(|rest| rest.split_once('_'))
+        .map(|(number, _)| number);
+    matches!(prefix, Some("xs" | "xp" | "xq"))
+        && number.is_some_and(|number| {
+            !number.is_empty() && number.chars().all(|digit| digit.is_ascii_digit())
+        })
+}
+
+/// Whether objects of the rule can be named with apgcodes: two-state rules on the plane,
+/// unchanged by rotations and reflections.
+pub fn supports(rule: &Rule) -> bool {
+    rule.topology() == &Topology::Plane
+        && rule.states() == 2
+        && rule.teams() == 1
+        && matches!(
+            rule.neighborhood(),
+            Neighborhood::Moore | Neighborhood::VonNeumann
+        )
+        && rule.table().is_none()
+        && rule.elementary().is_none()
+        && rule.block().is_none()
+        && rule.stochastic().is_none()
+}
+
+/// Encodes the cells in the extended Wechsler format, as seen from their top left corner.
+pub fn wechsler(cells: &[(i32, i32)]) -> String {
+    let line_min = cells.iter().map(|(line, _)| *line).min().unwrap_or(0);
+    let column_min = cells.iter().map(|(_, column)| *column).min().unwrap_or(0);
+    let mut strips: Vec<Vec<u8>> = vec![];
+    for (line, column) in cells {
+        let (line, column) = (line - line_min, (column - column_min) as usize);
+        let strip = (line / STRIP) as usize;
+        if strips.len() <= strip {
+            strips.resize(strip + 1, vec![]);
+        }
+        if strips[strip].len() <= column {
+            strips[strip].resize(column + 1, 0);
+        }
+        strips[strip][column] |= 1 << (line % STRIP);
+    }
+    let strips = strips
+        .iter()
+        .map(|strip| encode_strip(strip))
+        .collect::<Vec<_>>();
+    strips.join("z")
+}
+
+/// Decodes cells in the extended Wechsler format, the reverse of [`wechsler`].
+pub fn decode_wechsler(code: &str) -> std::result::Result<Vec<(i32, i32)>, ParseError> {
+    let invalid = |entry: char| {
+        ParseError::new(format!(
+            "unexpected '{}' in extended Wechsler code '{}'",
+            entry, code
+        ))
+    };
+    let mut cells = vec![];
+    let (mut strip, mut column) = (0, 0);
+    let mut entries = code.chars();
+    while let Some(entry) = entries.next() {
+        match entry {
+            'w' => column += 2,
+            'x' => column += 3,
+            'y' => {
+                let run = entries
+                    .next()
+                    .and_then(|run| DIGITS.iter().position(|digit| char::from(*digit) == run))
+                    .ok_or_else(|| invalid(entry))?;
+                column += 4 + run as i32;
+            }
+            'z' => {
+                strip += 1;
+                column = 0;
+            }
+            _ => {
+                let value = DIGITS[..32]
+                    .iter()
+                    .position(|digit| char::from(*digit) == entry)
+                    .ok_or_else(|| invalid(entry))?;
+                for bit in 0..STRIP {
+                    if value & (1 << bit) != 0 {
+                        cells.push((strip * STRIP + bit, column));
+                    }
+                }
+                column += 1;
+            }
+        }
+    }
+    cells.sort_unstable();
+    Ok(cells)
+}
+
+fn encode_strip(columns: &[u8]) -> String {
+    let mut code = String::new();
+    let mut zeros = 0;
+    for column in columns {
+        if *column == 0 {
+            zeros += 1;
+            continue;
+        }
+        push_zeros(&mut code, zeros);
+        zeros = 0;
+        code.push(char::from(DIGITS[usize::from(*column)]));
+    }
+    code
+}
+
+fn push_zeros(code: &mut String, mut zeros: usize) {
+    while zeros > 0 {
+        let run = zeros.min(39);
+        match run {
+            1 => code.push('0'),
+            2 => code.push('w'),
+            3 => code.push('x'),
+            _ => {
+                code.push('y');
+                code.push(char::from(DIGITS[run - 4]));
+            }
+        }
+        zeros -= run;
+    }
+}
+
+/// The eight rotations and reflections of the square, as the signs of the line and the
+/// column, and whether they are swapped.
+const ORIENTATIONS: [(i32, i32, bool); 8] = [
+    (1, 1, false),
+    (1, -1, false),
+    (-1, 1, false),
+    (-1, -1, false),
+    (1, 1, true),
+    (1, -1, true),
+    (-1, 1, true),
+    (-1, -1, true),
+];
+
+fn orientations(cells: &[(i32, i32)]) -> impl Iterator<Item = Vec<(i32, i32)>> + '_ {
+    ORIENTATIONS.iter().map(move |(lines, columns, swap)| {
+        let mut cells = cells
+            .iter()
+            .map(|(line, column)| match swap {
+                false => (line * lines, column * columns),
+                true => (column * lines, line * columns),
+            })
+            .collect::<Vec<_>>();
+        cells.sort_unstable();
+        cells
+    })
+}
+
+/// The shortest encoding of the phases in any orientation, the first in alphabetical order
+/// among the shortest ones, as in apgsearch.
+pub fn canonical(phases: &[Vec<(i32, i32)>]) -> String {
+    phases
+        .iter()
+        .flat_map(|phase| orientations(phase))
+        .map(|cells| wechsler(&cells))
+        .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+        .unwrap_or_default()
+}
+
+fn positions(universe: &Universe) -> Vec<(i32, i32)> {
+    universe
+        .entities
+        .iter()
+        .map(|entity| (entity.line, entity.column))
+        .collect()
+}
+
+/// Names a single object after running it on its own, e.g. `xq4_153` for the glider.
+pub fn classify(object: &Universe) -> String {
+    let mut universe = object.clone();
+    let mut detector = PeriodDetector::default();
+    let mut phases = vec![];
+    for _ in 0..=CLASSIFY_LIMIT {
+        phases.push(positions(&universe));
+        if let Some(behavior) = detector.observe(&universe) {
+            let prefix = match behavior {
+                Behavior::Empty => return UNKNOWN.to_string(),
+                Behavior::StillLife => format!("xs{}", universe.population()),
+                Behavior::Oscillator { period } => format!("xp{}", period),
+                Behavior::Spaceship { period, .. } => format!("xq{}", period),
+            };
+            let period = match behavior {
+                Behavior::Oscillator { period } | Behavior::Spaceship { period, .. } => period,
+                _ => 1,
+            };
+            let phases = &phases[phases.len() - period as usize..];
+            return format!("{}_{}", prefix, canonical(phases));
+        }
+        universe.step(1);
+    }
+    UNKNOWN.to_string()
+}
+
+impl Universe {
+    /// Reads an apgcode, such as `xs4_33` or `xq4_153`; the cells follow Conway's Life, which
+    /// can be changed afterwards.
+    pub fn from_apgcode(value: &str) -> std::result::Result<Self, ParseError> {
+        let value = value.trim();
+        if !matches(value) {
+            return Err(ParseError::new(format!("invalid apgcode '{}'", value)));
+        }
+        let (prefix, code) = value.split_once('_').unwrap_or((value, ""));
+        let cells = decode_wechsler(code)?;
+        if cells.is_empty() {
+            return Err(ParseError::new(format!("empty apgcode '{}'", value)));
+        }
+        // still lifes give their population
+        if prefix.starts_with("xs") && prefix[2..] != cells.len().to_string() {
+            return Err(ParseError::new(format!(
+                "apgcode '{}' has {} cells",
+                value,
+                cells.len()
+            )));
+        }
+        let entities = cells
+            .iter()
+            .map(|(line, column)| {
+                Rc::new(Entity {
+                    line: *line,
+                    column: *column,
+                    state: 1,
+                    team: 0,
+                })
+            })
+            .collect::<BTreeSet<_>>();
+        Ok(Universe {
+            entities,
+            ..Universe::new("")
+        })
+    }
+
+    /// The apgcode of the cells taken as a single object, if the rule supports apgcodes and
+    /// the cells settle into a still life, an oscillator or a spaceship.
+    pub fn to_apgcode(&self) -> Option<String> {
+        if !supports(&self.rule) || self.entities.is_empty() {
+            return None;
+        }
+        Some(classify(self)).filter(|code| code != UNKNOWN)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apgcode(matrix: &str) -> String {
+        classify(&Universe::new(matrix))
+    }
+
+    #[test]
+    fn test_wechsler() {
+        assert_eq!("33", wechsler(&[(0, 0), (0, 1), (1, 0), (1, 1)]));
+        assert_eq!("1w1", wechsler(&[(0, 0), (0, 3)]));
+        assert_eq!("1y01", wechsler(&[(0, 0), (0, 5)]));
+        assert_eq!("1yz1", wechsler(&[(0, 0), (0, 40)]));
+        assert_eq!("1yz01", wechsler(&[(0, 0), (0, 41)]));
+        assert_eq!("1z1", wechsler(&[(0, 0), (5, 0)]));
+        assert_eq!("1zz1", wechsler(&[(0, 0), (10, 0)]));
+        assert_eq!("h", wechsler(&[(3, 1), (7, 1)]));
+    }
+
+    #[test]
+    fn test_still_lifes() {
+        assert_eq!("xs4_33", apgcode("11\n11"));
+        assert_eq!("xs6_696", apgcode("0110\n1001\n0110"));
+        assert_eq!("xs5_253", apgcode("110\n101\n010"));
+        assert_eq!("xs6_356", apgcode("110\n101\n011"));
+        assert_eq!("xs4_252", apgcode("010\n101\n010"));
+        assert_eq!("xs7_2596", apgcode("0110\n1001\n0101\n0010"));
+        assert_eq!("xs8_6996", apgcode("0110\n1001\n1001\n0110"));
+    }
+
+    #[test]
+    fn test_oscillators() {
+        assert_eq!("xp2_7", apgcode("111"));
+        assert_eq!("xp2_7", apgcode("1\n1\n1"));
+        let pentadecathlon = "111\n101\n111\n111\n111\n111\n101\n111";
+        assert_eq!("xp15_4r4z4r4", apgcode(pentadecathlon));
+    }
+
+    #[test]
+    fn test_spaceships() {
+        assert_eq!("xq4_153", apgcode("010\n001\n111"));
+        assert_eq!("xq4_153", apgcode("111\n100\n010"));
+        assert_eq!("xq4_6frc", apgcode("01111\n10001\n00001\n10010"));
+    }
+
+    #[test]
+    fn test_decode() {
+        for code in [
+            "33", "1w1", "1y01", "1yz1", "1yz01", "1z1", "1zz1", "h", "4r4z4r4",
+        ] {
+            assert_eq!(code, wechsler(&decode_wechsler(code).unwrap()));
+        }
+        assert_eq!(
+            vec![(0, 0), (4, 0), (4, 5)],
+            decode_wechsler("hy0g").unwrap()
+        );
+        assert!(decode_wechsler("1w-").is_err());
+        assert!(decode_wechsler("1y").is_err());
+        // only five lines in a strip
+        assert!(decode_wechsler("w").unwrap().is_empty());
+        assert!(decode_wechsler("1v").is_ok());
+        assert!(decode_wechsler("1!").is_err());
+    }
+
+    #[test]
+    fn test_universe() {
+        for code in [
+            "xs4_33",
+            "xs6_696",
+            "xp2_7",
+            "xp2_318c",
+            "xp15_4r4z4r4",
+            "xq4_153",
+            "xq4_6frc",
+            "xq4_27dee6",
+        ] {
+            let universe = Universe::from_apgcode(code).unwrap();
+            assert_eq!(Some(code.to_string()), universe.to_apgcode());
+        }
+        assert_eq!(
+            Universe::new("10\n11\n11"),
+            Universe::from_apgcode("xs5_76").unwrap()
+        );
+        assert_eq!(Some("loaf"), name("xs7_2596"));
+        assert_eq!(None, name("xs5_76"));
+        assert!(matches(" xq4_153\n"));
+        assert_eq!(
+            Universe::from_apgcode("xq4_153"),
+            Universe::from_pattern("xq4_153\n")
+        );
+        assert!(!matches("x = 3, y = 3"));
+        assert!(!matches("xs_33"));
+        assert!(Universe::from_apgcode("xs5_33").is_err());
+        assert!(Universe::from_apgcode("xp2_").is_err());
+        assert!(Universe::from_apgcode("xp2_7!").is_err());
+        assert_eq!(None, Universe::new("1").to_apgcode());
+        let mut torus = Universe::new("11\n11");
+        torus.rule = Rule::parse("B3/S23:T10,10").unwrap();
+        assert_eq!(None, torus.to_apgcode());
+    }
+
+    #[test]
+    fn test_unknown() {
+        // dies out on its own
+        assert_eq!(UNKNOWN, apgcode("1"));
+        assert!(!supports(&Rule::parse("B3/S23:T10,10").unwrap()));
+        assert!(supports(&Rule::parse("B36/S23").unwrap()));
+    }
+}
@@ -0,0 +1,151 @@
+use crate::universe::ParseError;
+use std::fmt::{Display, Formatter, Result};
+
+/// Well known block rules, written with their name.
+const NAMED: [(&str, [u8; 16]); 3] = [
+    (
+        "BBM",
+        [0, 8, 4, 3, 2, 5, 9, 7, 1, 6, 10, 11, 12, 13, 14, 15],
+    ),
+    (
+        "Critters",
+        [15, 14, 13, 3, 11, 5, 6, 1, 7, 9, 10, 2, 12, 4, 8, 0],
+    ),
+    (
+        "Tron",
+        [15, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 0],
+    ),
+];
+
+/// Block cellular automaton on the Margolus neighborhood, e.g.
+/// `M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15` for the Billiard Ball Machine (`BBM`).
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct BlockRule {
+    table: [u8; 16],
+}
+
+impl BlockRule {
+    /// Whether the rule counts look like a block rule rather than B/S notation.
+    pub fn matches(value: &str) -> bool {
+        let mut entries = value.chars();
+        let numbered = matches!(entries.next(), Some('M' | 'm'))
+            && entries.next().is_some_and(|entry| entry.is_ascii_digit());
+        numbered
+            || NAMED
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case(value.trim()))
+    }
+
+    pub fn parse(value: &str) -> std::result::Result<Self, ParseError> {
+        if let Some((_, table)) = NAMED
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(value.trim()))
+        {
+            return Ok(BlockRule { table: *table });
+        }
+        let invalid = || {
+            ParseError::new(format!(
+                "block rules list the 16 numbers from 0 to 15 in some order: '{}'",
+                value
+            ))
+        };
+        let mut table = [0; 16];
+        let mut entries = value
+            .trim()
+            .strip_prefix(['M', 'm'])
+            .ok_or_else(invalid)?
+            .split(',');
+        for entry in table.iter_mut() {
+            *entry = entries
+                .next()
+                .and_then(|entry| entry.trim().parse::<u8>().ok())
+                .filter(|entry| *entry < 16)
+                .ok_or_else(invalid)?;
+        }
+        let permutation = (0..16).all(|block| table.contains(&block));
+        if entries.next().is_some() || !permutation {
+            return Err(invalid());
+        }
+        Ok(BlockRule { table })
+    }
+
+    /// The new content of a block.
+    pub fn next(&self, block: u8) -> u8 {
+        self.table[usize::from(block)]
+    }
+
+    /// The rule undoing this one.
+    pub fn inverse(&self) -> Self {
+        let mut table = [0; 16];
+        for (block, next) in self.table.iter().enumerate() {
+            table[usize::from(*next)] = block as u8;
+        }
+        BlockRule { table }
+    }
+
+    /// Whether the rule swaps empty and full blocks, so the plane stores every other complement.
+    pub fn strobes(&self) -> bool {
+        self.next(0) == 15 && self.next(15) == 0
+    }
+}
+
+impl Display for BlockRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if let Some((name, _)) = NAMED.iter().find(|(_, table)| *table == self.table) {
+            return write!(f, "{}", name);
+        }
+        let table = self
+            .table
+            .iter()
+            .map(|block| block.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "M{}", table.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::universe::rule::Rule;
+
+    #[test]
+    fn test_parse() {
+        let bbm = BlockRule::parse("M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15").unwrap();
+        assert_eq!("BBM", bbm.to_string());
+        assert_eq!(bbm, BlockRule::parse("bbm").unwrap());
+        // a single ball moves diagonally across its block
+        assert_eq!(8, bbm.next(1));
+        assert_eq!(bbm, bbm.inverse());
+        let critters = BlockRule::parse("Critters").unwrap();
+        assert!(critters.strobes());
+        assert!(!bbm.strobes());
+        assert_eq!(
+            "M15,7,11,3,13,5,6,8,14,9,10,4,12,2,1,0",
+            critters.inverse().to_string()
+        );
+        assert_eq!(critters, critters.inverse().inverse());
+        assert!(BlockRule::matches("M1,0,2,3,4,5,6,7,8,9,10,11,12,13,14,15"));
+        assert!(BlockRule::matches("Tron"));
+        assert!(!BlockRule::matches("B3/S23"));
+        // not a permutation
+        assert!(BlockRule::parse("M0,0,2,3,4,5,6,7,8,9,10,11,12,13,14,15").is_err());
+        assert!(BlockRule::parse("M0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,16").is_err());
+        assert!(BlockRule::parse("M0,1,2,3,4,5,6,7,8,9,10,11,12,13,14").is_err());
+        assert!(BlockRule::parse("M0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,0").is_err());
+        assert!(BlockRule::parse("ÉBM").is_err());
+        assert!(BlockRule::parse("É0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15").is_err());
+        assert!(Rule::parse("ÉBM").is_err());
+    }
+
+    #[test]
+    fn test_topologies() {
+        assert_eq!("Critters", Rule::parse("critters").unwrap().to_string());
+        assert_eq!("BBM:T10,20", Rule::parse("BBM:T10,20").unwrap().to_string());
+        assert!(Rule::parse("BBM:T11,20").is_err());
+        assert!(Rule::parse("BBM:P10,20").is_err());
+        // empty blocks come to life, but full blocks do not die
+        let rule = "M1,0,2,3,4,5,6,7,8,9,10,11,12,13,14,15";
+        assert!(Rule::parse(rule).is_err());
+        assert!(Rule::parse(&format!("{}:T10,10", rule)).is_ok());
+    }
+}
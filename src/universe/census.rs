@@ -0,0 +1,189 @@
+use crate::engine::LifeEngine;
+use crate::universe::apgcode::{self, classify};
+use crate::universe::rule::Rule;
+use crate::universe::{Entity, Universe};
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+
+/// Generations run to check that the parts of a group do not interact.
+const SPLIT_GENERATIONS: usize = 32;
+
+/// Counts of the objects of a universe by apgcode, as in apgsearch.
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct Census {
+    counts: BTreeMap<String, usize>,
+}
+
+impl Census {
+    /// The census of the objects, if they can be named with apgcodes.
+    pub fn take(universe: &Universe) -> Option<Self> {
+        if !apgcode::supports(&universe.rule) {
+            return None;
+        }
+        let radius = universe.rule.radius();
+        let cells = universe
+            .entities
+            .iter()
+            .map(|entity| (entity.line, entity.column))
+            .collect::<BTreeSet<_>>();
+        let mut census = Census::default();
+        for group in components(&cells, 2 * radius) {
+            for object in split(&universe.rule, &group, radius) {
+                *census.counts.entry(classify(&object)).or_default() += 1;
+            }
+        }
+        Some(census)
+    }
+
+    /// The apgcodes with their counts, the most common first.
+    pub fn objects(&self) -> Vec<(&str, usize)> {
+        let mut objects = self
+            .counts
+            .iter()
+            .map(|(code, count)| (code.as_str(), *count))
+            .collect::<Vec<_>>();
+        objects.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        objects
+    }
+
+    pub fn count(&self, code: &str) -> usize {
+        self.counts.get(code).copied().unwrap_or(0)
+    }
+
+    /// Number of objects.
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+}
+
+fn components(cells: &BTreeSet<(i32, i32)>, distance: i32) -> Vec<BTreeSet<(i32, i32)>> {
+    let mut remaining = cells.clone();
+    let mut components = vec![];
+    while let Some(first) = remaining.pop_first() {
+        let mut component = BTreeSet::from([first]);
+        let mut pending = vec![first];
+        while let Some((line, column)) = pending.pop() {
+            for neighbor_line in line - distance..=line + distance {
+                for neighbor_column in column - distance..=column + distance {
+                    if remaining.remove(&(neighbor_line, neighbor_column)) {
+                        component.insert((neighbor_line, neighbor_column));
+                        pending.push((neighbor_line, neighbor_column));
+                    }
+                }
+            }
+        }
+        components.push(component);
+    }
+    components
+}
+
+fn object(rule: &Rule, cells: &BTreeSet<(i32, i32)>) -> Universe {
+    let entities = cells
+        .iter()
+        .map(|(line, column)| {
+            Rc::new(Entity {
+                line: *line,
+                column: *column,
+                state: 1,
+                team: 0,
+            })
+        })
+        .collect();
+    Universe {
+        entities,
+        rule: rule.clone(),
+        ..Universe::new("")
+    }
+}
+
+fn positions(universe: &Universe) -> BTreeSet<(i32, i32)> {
+    universe
+        .entities
+        .iter()
+        .map(|entity| (entity.line, entity.column))
+        .collect()
+}
+
+fn split(rule: &Rule, group: &BTreeSet<(i32, i32)>, radius: i32) -> Vec<Universe> {
+    let parts = components(group, radius)
+        .iter()
+        .map(|part| object(rule, part))
+        .collect::<Vec<_>>();
+    if parts.len() == 1 {
+        return parts;
+    }
+    let mut whole = object(rule, group);
+    let mut evolved = parts.clone();
+    for _ in 0..SPLIT_GENERATIONS {
+        whole.step(1);
+        let mut cells = BTreeSet::new();
+        for part in evolved.iter_mut() {
+            part.step(1);
+            cells.extend(positions(part));
+        }
+        if cells != positions(&whole) {
+            return vec![object(rule, group)];
+        }
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_census() {
+        let universe = Universe::new(
+            r#"
+11000000000000000000000000000
+11000000000000000000000000000
+00000000000000000000000000000
+00000000000000000000000000000
+00001110000000000010000000000
+00000000000000000001000000000
+00000000000000000111000000000
+00000000000000000000000000000
+00000000000000000000000000000
+11011000000000000000000000000
+11011000000000000000000000000
+00000000000000000000000000000
+00000000000000000000000000000
+00000000000000000000000000000
+00000000000000000000000000000
+00000000000000000000000000000
+00000000000000011100011100000
+00000000000000000000000000000
+00000000000001000010100001000
+00000000000001000010100001000
+00000000000001000010100001000
+00000000000000011100011100000
+00000000000000000000000000000
+00000000000000011100011100000
+00000000000001000010100001000
+00000000000001000010100001000
+00000000000001000010100001000
+00000000000000000000000000000
+00000000000000011100011100000
+        "#,
+        );
+        let census = Census::take(&universe).unwrap();
+        // the two blocks next to each other are counted apart, the pulsar is a single object
+        assert_eq!(3, census.count("xs4_33"));
+        assert_eq!(1, census.count("xp2_7"));
+        assert_eq!(1, census.count("xq4_153"));
+        assert_eq!(6, census.total());
+        assert_eq!(
+            1,
+            census
+                .objects()
+                .iter()
+                .filter(|(code, _)| code.starts_with("xp3_"))
+                .count()
+        );
+        assert_eq!(("xs4_33", 3), census.objects()[0]);
+        let mut torus = universe;
+        torus.rule = Rule::parse("B3/S23:T40,40").unwrap();
+        assert_eq!(None, Census::take(&torus));
+    }
+}
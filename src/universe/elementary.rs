@@ -0,0 +1,180 @@
+//! Wolfram's elementary cellular automata, such as `W30` or `W110`.
+//!
+//! The cells of a row are set from the three cells above them: bit `4 * left + 2 * center +
+//! right` of the rule number is the new state. Each generation adds a row below the previous
+//! one, drawing the history of the automaton from top to bottom: the row of generation `g` is
+//! line `g`.
+
+use crate::universe::{Entity, Universe};
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+impl Universe {
+    /// Adds the row of the current generation, computed from the row above it, where missing
+    /// cells are dead.
+    pub(crate) fn add_row(&mut self, number: u8) {
+        let line = match i32::try_from(self.generation) {
+            Ok(generation) if generation > 0 => generation - 1,
+            _ => return,
+        };
+        let topology = self.rule.topology().clone();
+        let columns = match topology.size() {
+            Some((_, height)) if line + 1 >= height => return,
+            Some((width, _)) => 0..width,
+            None => {
+                let start = |line| Entity {
+                    line,
+                    column: i32::MIN,
+                    state: 0,
+                    team: 0,
+                };
+                let mut row = self.entities.range(start(line)..start(line + 1));
+                match (row.next(), row.next_back()) {
+                    (Some(first), Some(last)) => (first.column - 1)..(last.column + 2),
+                    (Some(single), None) => (single.column - 1)..(single.column + 2),
+                    _ => 0..0,
+                }
+            }
+        };
+        let alive = |column: i32| {
+            topology
+                .wrap(line, column)
+                .is_some_and(|(line, column)| self.state(line, column) == 1) as u8
+        };
+        let row = columns
+            .filter(|column| {
+                let pattern = alive(column - 1) << 2 | alive(*column) << 1 | alive(column + 1);
+                number >> pattern & 1 != 0
+            })
+            .collect::<Vec<_>>();
+        for column in row {
+            self.entities.insert(Rc::new(Entity {
+                line: line + 1,
+                column,
+                state: 1,
+                team: 0,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::LifeEngine;
+    use crate::universe::rule::Rule;
+    use crate::universe::Universe;
+
+    fn rows(universe: &Universe) -> Vec<String> {
+        let boundaries = universe.boundaries();
+        (boundaries.line_min..=boundaries.line_max)
+            .map(|line| {
+                (boundaries.column_min..=boundaries.column_max)
+                    .map(|column| {
+                        if universe.get_cell(line, column) {
+                            '1'
+                        } else {
+                            '0'
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("W30", Rule::parse("W30").unwrap().to_string());
+        assert_eq!(Some(110), Rule::parse("w110").unwrap().elementary());
+        assert_eq!("W31:T40,20", Rule::parse("W31:T40,20").unwrap().to_string());
+        // odd rules turn empty neighborhoods on
+        assert!(Rule::parse("W31").is_err());
+        assert!(Rule::parse("W256").is_err());
+        assert!(Rule::parse("W-1").is_err());
+    }
+
+    #[test]
+    fn test_single_cell() {
+        let mut universe = Universe::new("1");
+        universe.rule = Rule::parse("W90").unwrap();
+        universe.step(3);
+        assert_eq!(
+            vec!["0001000", "0010100", "0100010", "1010101"],
+            rows(&universe)
+        );
+        let mut universe = Universe::new("1");
+        universe.rule = Rule::parse("W30").unwrap();
+        universe.step(3);
+        assert_eq!(
+            vec!["0001000", "0011100", "0110010", "1101111"],
+            rows(&universe)
+        );
+    }
+
+    #[test]
+    fn test_finite_grid() {
+        let positions = |universe: &Universe| {
+            universe
+                .entities
+                .iter()
+                .map(|entity| (entity.line, entity.column))
+                .collect::<Vec<_>>()
+        };
+        // W2 moves the cell to the left, wrapping around the torus, and stops at the bottom
+        let mut universe = Universe::new("1");
+        universe.rule = Rule::parse("W2:T4,3").unwrap();
+        universe.step(5);
+        assert_eq!(vec![(0, 0), (1, 3), (2, 2)], positions(&universe));
+        // empty neighborhoods turn on with W1, the cells beyond the edges of the plane are empty
+        let mut universe = Universe::new("1");
+        universe.rule = Rule::parse("W1:P4,3").unwrap();
+        universe.step(2);
+        assert_eq!(vec![(0, 0), (1, 2), (1, 3), (2, 0)], positions(&universe));
+        // the full row dies with W1, and the next one is computed from its dead cells
+        let mut universe = Universe::new("1111");
+        universe.rule = Rule::parse("W1:T4,5").unwrap();
+        universe.step(2);
+        assert_eq!(8, universe.entities.len());
+        assert_eq!(
+            Some(2),
+            universe.entities.iter().next_back().map(|e| e.line)
+        );
+        universe.step(3);
+        let full = |line| (0..4).map(move |column| (line, column));
+        assert_eq!(
+            full(0).chain(full(2)).chain(full(4)).collect::<Vec<_>>(),
+            positions(&universe)
+        );
+        // the row above the first one is empty
+        let mut universe = Universe::new("1");
+        universe.rule = Rule::parse("W90").unwrap();
+        universe.generation = 3;
+        universe.step(1);
+        assert_eq!(vec![(0, 0)], positions(&universe));
+    }
+
+    #[test]
+    fn test_matches_tick() {
+        // the generic transition gives the same history when every row is recomputed
+        let mut universe = Universe::new("1");
+        universe.rule = Rule::parse("W110").unwrap();
+        universe.step(20);
+        let rule = universe.rule.clone();
+        let mut next = Universe::new("");
+        for entity in &universe.entities {
+            let configuration = [(-1, -1), (-1, 0), (-1, 1)]
+                .iter()
+                .enumerate()
+                .filter(|(_, (lines, columns))| {
+                    universe.get_cell(entity.line + lines, entity.column + columns)
+                })
+                .fold(0, |configuration, (index, _)| configuration | 0x80 >> index);
+            let state = if entity.line == 0 {
+                1
+            } else {
+                rule.next_state(0, configuration)
+            };
+            next.set_state(entity.line, entity.column, state);
+        }
+        assert_eq!(universe.cells(), next.cells());
+    }
+}
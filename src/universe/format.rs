@@ -0,0 +1,147 @@
+use crate::universe::apgcode;
+use crate::universe::life::{LIFE_105_HEADER, LIFE_106_HEADER};
+use crate::universe::macrocell::MACROCELL_HEADER;
+use crate::universe::{ParseError, Universe};
+
+/// Pattern file formats understood by [`Universe::from_pattern`].
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum Format {
+    Rle,
+    Plaintext,
+    Life105,
+    Life106,
+    Macrocell,
+}
+
+impl Format {
+    /// Guesses the format of a pattern file from its content.
+    pub fn detect(value: &str) -> Option<Self> {
+        let lines: Vec<&str> = value
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+        let first = lines.first()?;
+        if first.starts_with(MACROCELL_HEADER) {
+            return Some(Format::Macrocell);
+        }
+        if first.starts_with(LIFE_106_HEADER) {
+            return Some(Format::Life106);
+        }
+        if first.starts_with(LIFE_105_HEADER) {
+            return Some(Format::Life105);
+        }
+        if first.starts_with('!') {
+            return Some(Format::Plaintext);
+        }
+        let mut rows = lines
+            .iter()
+            .filter(|line| !line.starts_with('#'))
+            .peekable();
+        let first_row = rows.peek()?;
+        if first_row.starts_with('x') && first_row.contains('=') {
+            return Some(Format::Rle);
+        }
+        if rows
+            .clone()
+            .all(|row| row.chars().all(|entry| matches!(entry, '.' | 'O' | '*')))
+        {
+            return Some(Format::Plaintext);
+        }
+        if rows.all(|row| {
+            row.split_whitespace().count() == 2
+                && row
+                    .split_whitespace()
+                    .all(|value| value.parse::<i32>().is_ok())
+        }) {
+            return Some(Format::Life106);
+        }
+        None
+    }
+}
+
+impl Universe {
+    /// Reads a pattern in any of the supported [`Format`]s, detected from its content, or an
+    /// apgcode.
+    pub fn from_pattern(value: &str) -> std::result::Result<Self, ParseError> {
+        if apgcode::matches(value) {
+            return Universe::from_apgcode(value);
+        }
+        match Format::detect(value) {
+            Some(Format::Rle) => Universe::from_rle(value),
+            Some(Format::Plaintext) => Universe::from_plaintext(value),
+            Some(Format::Life105) => Universe::from_life_105(value),
+            Some(Format::Life106) => Universe::from_life_106(value),
+            Some(Format::Macrocell) => Universe::from_macrocell(value),
+            None => Err(ParseError::new("unknown pattern format".to_string())),
+        }
+    }
+
+    /// Writes the pattern in the format, or fails when the format cannot hold its cells or its
+    /// rule; RLE holds them all.
+    pub fn to_pattern(&self, format: Format) -> std::result::Result<String, ParseError> {
+        match format {
+            Format::Rle => Ok(self.to_rle()),
+            Format::Plaintext => self.to_plaintext(),
+            Format::Life105 => self.to_life_105(),
+            Format::Life106 => self.to_life_106(),
+            Format::Macrocell => Ok(self.to_macrocell()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            Some(Format::Rle),
+            Format::detect("x = 3, y = 3\nbo$2bo$3o!")
+        );
+        assert_eq!(
+            Some(Format::Rle),
+            Format::detect("#N Glider\n#C comment\nx = 3, y = 3\nbo$2bo$3o!")
+        );
+        assert_eq!(
+            Some(Format::Plaintext),
+            Format::detect("!Name: Glider\n.O\n..O\nOOO")
+        );
+        assert_eq!(Some(Format::Plaintext), Format::detect(".O\n..O\nOOO"));
+        assert_eq!(
+            Some(Format::Life105),
+            Format::detect("#Life 1.05\n#P -1 -1\n.*\n..*\n***")
+        );
+        assert_eq!(
+            Some(Format::Life106),
+            Format::detect("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1")
+        );
+        assert_eq!(Some(Format::Life106), Format::detect("0 -1\n1 0\n-1 1"));
+        assert_eq!(None, Format::detect(""));
+        assert_eq!(None, Format::detect("hello world"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let glider = Universe::new(
+            r#"
+010
+001
+111
+        "#,
+        );
+        for format in [
+            Format::Rle,
+            Format::Plaintext,
+            Format::Life105,
+            Format::Life106,
+            Format::Macrocell,
+        ] {
+            let pattern = glider.to_pattern(format).unwrap();
+            assert_eq!(Some(format), Format::detect(&pattern));
+            assert_eq!(glider, Universe::from_pattern(&pattern).unwrap());
+        }
+        assert!(Universe::from_pattern("hello world").is_err());
+    }
+}
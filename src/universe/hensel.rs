@@ -0,0 +1,94 @@
+//! Hensel notation for isotropic non-totalistic rules, e.g. `B2-a/S12`.
+//!
+//! A neighbor count can be followed by letters selecting some arrangements of the live
+//! neighbors, up to rotations and reflections, or by `-` and the letters to leave out.
+//! Configurations are bit masks of the eight neighbors in reading order, the most significant
+//! bit being the top left cell.
+
+/// Valid letters for 0 to 4 live neighbors; counts above 4 use the letters of `8 - count`.
+const LETTERS: [&str; 5] = ["", "ce", "ceaikn", "ceaiknjqry", "ceaiknjqrytwz"];
+
+/// One configuration for each letter of [`LETTERS`]. Counts above 4 use the complement of the
+/// configuration for `8 - count`.
+const REPRESENTATIVES: [&[u8]; 5] = [
+    &[0x00],
+    &[0x80, 0x40],
+    &[0xa0, 0x50, 0xc0, 0x18, 0x88, 0x24],
+    &[0xa4, 0x58, 0xd0, 0xe0, 0x4c, 0xb0, 0x70, 0x64, 0x98, 0x8c],
+    &[
+        0xa5, 0x5a, 0xf0, 0xb8, 0xcc, 0xe4, 0x5c, 0x6c, 0xd8, 0xac, 0x9c, 0x74, 0x3c,
+    ],
+];
+
+/// Where each neighbor goes after a quarter turn clockwise.
+const ROTATION: [usize; 8] = [2, 4, 7, 1, 6, 0, 3, 5];
+/// Where each neighbor goes when mirrored left to right.
+const REFLECTION: [usize; 8] = [2, 1, 0, 4, 3, 7, 6, 5];
+
+fn permute(configuration: u8, permutation: &[usize; 8]) -> u8 {
+    permutation
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| configuration & (0x80 >> index) != 0)
+        .fold(0, |result, (_, target)| result | (0x80 >> target))
+}
+
+/// The letters that can follow a neighbor count.
+pub fn letters(count: usize) -> &'static str {
+    LETTERS[count.min(8 - count)]
+}
+
+/// All the rotations and reflections of the arrangement named by the letter, or `None` if
+/// the letter is not valid for the count.
+pub fn configurations(count: usize, letter: char) -> Option<Vec<u8>> {
+    let index = letters(count).find(letter)?;
+    let mut configuration = REPRESENTATIVES[count.min(8 - count)][index];
+    if count > 4 {
+        configuration = !configuration;
+    }
+    let mut configurations = vec![];
+    for _ in 0..4 {
+        configuration = permute(configuration, &ROTATION);
+        for symmetry in [configuration, permute(configuration, &REFLECTION)] {
+            if !configurations.contains(&symmetry) {
+                configurations.push(symmetry);
+            }
+        }
+    }
+    Some(configurations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configurations() {
+        // 2e: two orthogonal neighbors at a right angle
+        let mut two_e = configurations(2, 'e').unwrap();
+        two_e.sort_unstable();
+        assert_eq!(vec![0x0a, 0x12, 0x48, 0x50], two_e);
+        // 7c: everything but a corner
+        let mut seven_c = configurations(7, 'c').unwrap();
+        seven_c.sort_unstable();
+        assert_eq!(vec![0x7f, 0xdf, 0xfb, 0xfe], seven_c);
+        assert_eq!(None, configurations(1, 'a'));
+        assert_eq!(None, configurations(0, 'c'));
+        // the letters of each count split its configurations
+        for count in 0..9 {
+            let mut all = letters(count)
+                .chars()
+                .flat_map(|letter| configurations(count, letter).unwrap())
+                .collect::<Vec<_>>();
+            all.sort_unstable();
+            let expected = (0..=255)
+                .filter(|configuration: &u8| configuration.count_ones() as usize == count)
+                .collect::<Vec<_>>();
+            if count == 0 || count == 8 {
+                assert!(all.is_empty());
+            } else {
+                assert_eq!(expected, all, "count {}", count);
+            }
+        }
+    }
+}
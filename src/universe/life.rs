@@ -0,0 +1,173 @@
+use crate::universe::rule::Rule;
+use crate::universe::{Boundaries, Entity, ParseError, Universe};
+use std::rc::Rc;
+
+pub const LIFE_105_HEADER: &str = "#Life 1.05";
+pub const LIFE_106_HEADER: &str = "#Life 1.06";
+
+fn parse_coordinates(value: &str) -> Option<(i32, i32)> {
+    let mut values = value.split_whitespace().map(|entry| entry.parse::<i32>());
+    match (values.next(), values.next(), values.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) => Some((x, y)),
+        _ => None,
+    }
+}
+
+impl Universe {
+    /// Reads a Life 1.05 pattern: blocks of `.`/`*` rows, each positioned by a `#P x y` line.
+    pub fn from_life_105(value: &str) -> std::result::Result<Self, ParseError> {
+        let mut universe = Universe::new("");
+        let (mut x, mut line) = (0, 0);
+        for row in value.lines().map(|row| row.trim()) {
+            if let Some(position) = row.strip_prefix("#P") {
+                let (column, first_line) = parse_coordinates(position)
+                    .ok_or_else(|| ParseError::new(format!("invalid block position '{}'", row)))?;
+                x = column;
+                line = first_line;
+            } else if let Some(rule) = row.strip_prefix("#R") {
+                universe.rule = Rule::parse(rule)?;
+            } else if row == "#N" {
+                universe.rule = Rule::conway();
+            } else if !row.starts_with('#') {
+                for (column, entry) in row.chars().enumerate() {
+                    match entry {
+                        '.' => {}
+                        '*' => {
+                            universe.entities.insert(Rc::new(Entity {
+                                line,
+                                column: x + column as i32,
+                                state: 1,
+                                team: 0,
+                            }));
+                        }
+                        _ => {
+                            return Err(ParseError::new(format!(
+                                "unexpected '{}' in Life 1.05 pattern",
+                                entry
+                            )))
+                        }
+                    }
+                }
+                line += 1;
+            }
+        }
+        Ok(universe)
+    }
+
+    /// Reads a Life 1.06 pattern: one `x y` coordinate pair per live cell.
+    pub fn from_life_106(value: &str) -> std::result::Result<Self, ParseError> {
+        let mut universe = Universe::new("");
+        for row in value.lines().map(|row| row.trim()) {
+            if row.is_empty() || row.starts_with('#') {
+                continue;
+            }
+            let (column, line) = parse_coordinates(row)
+                .ok_or_else(|| ParseError::new(format!("invalid cell coordinates '{}'", row)))?;
+            universe.entities.insert(Rc::new(Entity {
+                line,
+                column,
+                state: 1,
+                team: 0,
+            }));
+        }
+        Ok(universe)
+    }
+
+    /// Writes the pattern in Life 1.05 format, as a single block of two-state cells.
+    pub fn to_life_105(&self) -> std::result::Result<String, ParseError> {
+        self.check_live_cells("Life 1.05", false)?;
+        let mut result = format!("{}\n", LIFE_105_HEADER);
+        if self.rule == Rule::conway() {
+            result.push_str("#N\n");
+        } else {
+            result.push_str(&format!("#R {}\n", self.rule));
+        }
+        if self.entities.is_empty() {
+            return Ok(result);
+        }
+        let Boundaries {
+            line_min,
+            column_min,
+            ..
+        } = self.boundaries();
+        result.push_str(&format!("#P {} {}\n", column_min, line_min));
+        result.push_str(&self.to_rows('.', '*'));
+        Ok(result)
+    }
+
+    /// Writes the pattern in Life 1.06 format, which only holds live cells of Conway's Life.
+    pub fn to_life_106(&self) -> std::result::Result<String, ParseError> {
+        self.check_live_cells("Life 1.06", true)?;
+        let mut result = format!("{}\n", LIFE_106_HEADER);
+        for entity in &self.entities {
+            result.push_str(&format!("{} {}\n", entity.column, entity.line));
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_life_105() {
+        let universe = Universe::from_life_105(
+            r#"#Life 1.05
+#D Glider
+#R 23/36
+#P -1 -1
+.*
+..*
+***
+"#,
+        )
+        .unwrap();
+        assert_eq!("B36/S23", universe.rule.to_string());
+        assert_eq!(5, universe.entities.len());
+        assert!(universe.entities.contains(&Entity {
+            line: -1,
+            column: 0,
+            state: 1,
+            team: 0
+        }));
+        assert!(universe.entities.contains(&Entity {
+            line: 1,
+            column: 1,
+            state: 1,
+            team: 0
+        }));
+        assert_eq!(
+            "#Life 1.05\n#R B36/S23\n#P -1 -1\n.*\n..*\n***\n",
+            universe.to_life_105().unwrap()
+        );
+        assert_eq!(
+            universe,
+            Universe::from_life_105(&universe.to_life_105().unwrap()).unwrap()
+        );
+        // Life 1.06 has no rule line
+        assert!(universe.to_life_106().is_err());
+        assert!(Universe::from_life_105("#Life 1.05\n#P 1\n*").is_err());
+    }
+
+    #[test]
+    fn test_life_106() {
+        let universe = Universe::from_life_106("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n").unwrap();
+        assert_eq!(5, universe.entities.len());
+        assert!(universe.entities.contains(&Entity {
+            line: 1,
+            column: -1,
+            state: 1,
+            team: 0
+        }));
+        assert_eq!(
+            "#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n",
+            universe.to_life_106().unwrap()
+        );
+        let mut dying = Universe::new("12\n11");
+        dying.rule = Rule::parse("/2/3").unwrap();
+        assert!(dying.to_life_105().is_err());
+        assert!(dying.to_life_106().is_err());
+        assert!(Universe::from_life_106("#Life 1.06\n0 -1 2\n").is_err());
+    }
+}
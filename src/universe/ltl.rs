@@ -0,0 +1,314 @@
+use crate::universe::topology::Topology;
+use crate::universe::{Boundaries, ParseError, Universe};
+use std::ops::RangeInclusive;
+
+/// Largest radius accepted, as the neighborhood grows with its square.
+const MAX_RADIUS: i32 = 100;
+
+/// The cells within the radius that are counted.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum Shape {
+    /// The whole square (`NM`).
+    Box,
+    /// The cells within the radius in Manhattan distance (`NN`).
+    Diamond,
+    /// The cells on the same line or column (`N+`).
+    Cross,
+}
+
+/// Larger than Life rule, counting live cells within a radius, e.g.
+/// `R5,C0,M1,S34..58,B34..45,NM` for Bosco's Rule.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct LargerThanLife {
+    radius: i32,
+    middle: bool,
+    survival: RangeInclusive<u32>,
+    birth: RangeInclusive<u32>,
+    shape: Shape,
+}
+
+fn parse_range(value: &str, rule: &str) -> std::result::Result<RangeInclusive<u32>, ParseError> {
+    let invalid = || ParseError::new(format!("invalid range '{}' in rule '{}'", value, rule));
+    let (min, max) = value.split_once("..").ok_or_else(invalid)?;
+    let min = min.parse::<u32>().map_err(|_| invalid())?;
+    let max = max.parse::<u32>().map_err(|_| invalid())?;
+    Ok(min..=max)
+}
+
+impl LargerThanLife {
+    /// Whether the rule counts look like Larger than Life rather than B/S notation.
+    pub fn matches(value: &str) -> bool {
+        let mut entries = value.chars();
+        matches!(entries.next(), Some('R' | 'r'))
+            && entries.next().is_some_and(|entry| entry.is_ascii_digit())
+    }
+
+    /// Parses the rule and its number of states.
+    pub fn parse(value: &str) -> std::result::Result<(Self, u8), ParseError> {
+        let invalid = || ParseError::new(format!("invalid rule '{}'", value));
+        let mut entries = value.split(',').map(|entry| entry.trim());
+        let mut next = |prefix: &str| {
+            entries
+                .next()
+                .and_then(|entry| {
+                    let key = entry.get(..prefix.len())?;
+                    if key.eq_ignore_ascii_case(prefix) {
+                        Some(&entry[prefix.len()..])
+                    } else {
+                        None
+                    }
+                })
+                .ok_or_else(invalid)
+        };
+        let radius = match next("R")?.parse::<i32>() {
+            Ok(radius) if (1..=MAX_RADIUS).contains(&radius) => radius,
+            _ => {
+                return Err(ParseError::new(format!(
+                    "the radius must be between 1 and {} in rule '{}'",
+                    MAX_RADIUS, value
+                )))
+            }
+        };
+        let states = match next("C")?.parse::<u8>() {
+            Ok(0) => 2,
+            Ok(states) if states >= 2 => states,
+            _ => return Err(invalid()),
+        };
+        let middle = match next("M")? {
+            "0" => false,
+            "1" => true,
+            _ => return Err(invalid()),
+        };
+        let survival = parse_range(next("S")?, value)?;
+        let birth = parse_range(next("B")?, value)?;
+        let shape = match next("N")? {
+            "M" | "m" => Shape::Box,
+            "N" | "n" => Shape::Diamond,
+            "+" => Shape::Cross,
+            shape => {
+                return Err(ParseError::new(format!(
+                    "unsupported neighborhood 'N{}' in rule '{}'",
+                    shape, value
+                )))
+            }
+        };
+        if next("").is_ok() {
+            return Err(invalid());
+        }
+        Ok((
+            LargerThanLife {
+                radius,
+                middle,
+                survival,
+                birth,
+                shape,
+            },
+            states,
+        ))
+    }
+
+    /// The rule in its usual notation, which includes the number of states.
+    pub fn format(&self, states: u16) -> String {
+        let shape = match self.shape {
+            Shape::Box => "M",
+            Shape::Diamond => "N",
+            Shape::Cross => "+",
+        };
+        format!(
+            "R{},C{},M{},S{}..{},B{}..{},N{}",
+            self.radius,
+            if states > 2 { states } else { 0 },
+            self.middle as u8,
+            self.survival.start(),
+            self.survival.end(),
+            self.birth.start(),
+            self.birth.end(),
+            shape
+        )
+    }
+
+    pub fn radius(&self) -> i32 {
+        self.radius
+    }
+
+    pub fn born(&self, count: u32) -> bool {
+        self.birth.contains(&count)
+    }
+
+    pub fn survives(&self, count: u32) -> bool {
+        self.survival.contains(&count)
+    }
+
+    /// Number of live neighbors of every cell in the area, in reading order.
+    pub fn counts(&self, universe: &Universe, area: &Boundaries) -> Vec<u32> {
+        let radius = self.radius;
+        let height = area.line_max - area.line_min + 1;
+        let width = area.column_max - area.column_min + 1;
+        // the area surrounded by the radius, with an extra line and column of zeros
+        let (lines, columns) = (height + 2 * radius, width + 2 * radius);
+        let (line_min, column_min) = (area.line_min - radius, area.column_min - radius);
+        let mut table = vec![0u32; ((lines + 1) * (columns + 1)) as usize];
+        let index = |line: i32, column: i32| (line * (columns + 1) + column) as usize;
+        let topology = universe.rule.topology();
+        if topology == &Topology::Plane {
+            for entity in universe.entities.iter().filter(|entity| entity.state == 1) {
+                let (line, column) = (entity.line - line_min, entity.column - column_min);
+                if (0..lines).contains(&line) && (0..columns).contains(&column) {
+                    table[index(line + 1, column + 1)] = 1;
+                }
+            }
+        } else {
+            for line in 0..lines {
+                for column in 0..columns {
+                    let alive = topology
+                        .wrap(line + line_min, column + column_min)
+                        .is_some_and(|(line, column)| universe.state(line, column) == 1);
+                    table[index(line + 1, column + 1)] = alive as u32;
+                }
+            }
+        }
+        for line in 1..=lines {
+            for column in 1..=columns {
+                table[index(line, column)] += table[index(line - 1, column)]
+                    + table[index(line, column - 1)]
+                    - table[index(line - 1, column - 1)];
+            }
+        }
+        // live cells in the rectangle, relative to the top left corner of the area
+        let sum = |top: i32, left: i32, bottom: i32, right: i32| {
+            let (top, left) = (top + radius, left + radius);
+            let (bottom, right) = (bottom + radius + 1, right + radius + 1);
+            table[index(bottom, right)] + table[index(top, left)]
+                - table[index(top, right)]
+                - table[index(bottom, left)]
+        };
+        let mut counts = Vec::with_capacity((height * width) as usize);
+        for line in 0..height {
+            for column in 0..width {
+                let count = match self.shape {
+                    Shape::Box => sum(
+                        line - radius,
+                        column - radius,
+                        line + radius,
+                        column + radius,
+                    ),
+                    Shape::Cross => {
+                        sum(line - radius, column, line + radius, column)
+                            + sum(line, column - radius, line, column + radius)
+                            - sum(line, column, line, column)
+                    }
+                    Shape::Diamond => (-radius..=radius)
+                        .map(|lines| {
+                            let reach = radius - lines.abs();
+                            sum(line + lines, column - reach, line + lines, column + reach)
+                        })
+                        .sum(),
+                };
+                let itself = if self.middle {
+                    0
+                } else {
+                    sum(line, column, line, column)
+                };
+                counts.push(count - itself);
+            }
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::LifeEngine;
+    use crate::universe::rule::Rule;
+
+    #[test]
+    fn test_parse() {
+        for rule in [
+            "R5,C0,M1,S34..58,B34..45,NM",
+            "R2,C3,M0,S2..4,B3..3,N+",
+            "R3,C0,M0,S1..2,B2..2,NN:T30,30",
+        ] {
+            assert_eq!(rule, Rule::parse(rule).unwrap().to_string());
+        }
+        let rule = Rule::parse("r5,c2,m1,s34..58,b34..45,nm").unwrap();
+        assert_eq!("R5,C0,M1,S34..58,B34..45,NM", rule.to_string());
+        assert_eq!(5, rule.radius());
+        assert_eq!(4, Rule::parse("R2,C4,M0,S2..4,B3..3,NM").unwrap().states());
+        assert!(Rule::parse("R0,C0,M1,S34..58,B34..45,NM").is_err());
+        assert!(Rule::parse("R101,C0,M1,S34..58,B34..45,NM").is_err());
+        assert!(Rule::parse("R5,C1,M1,S34..58,B34..45,NM").is_err());
+        assert!(Rule::parse("R5,C0,M2,S34..58,B34..45,NM").is_err());
+        assert!(Rule::parse("R5,C0,M1,S34,B34..45,NM").is_err());
+        assert!(Rule::parse("R5,C0,M1,S34..58,B34..45").is_err());
+        assert!(Rule::parse("R5,C0,M1,S34..58,B34..45,NC").is_err());
+        assert!(Rule::parse("R5,C0,M1,S34..58,B34..45,NM,X").is_err());
+        assert!(Rule::parse("R5,C0,M1,S34..58,B0..45,NM").is_err());
+        assert!(Rule::parse("R5,Ñ,M1,S34..58,B34..45,NM").is_err());
+    }
+
+    #[test]
+    fn test_range_one_is_life() {
+        for rule in ["R1,C0,M0,S2..3,B3..3,NM", "R1,C0,M1,S3..4,B3..3,NM"] {
+            let mut life = Universe::new(".11\n11\n.1");
+            let mut universe = life.clone();
+            universe.rule = Rule::parse(rule).unwrap();
+            life.step(30);
+            universe.step(30);
+            assert_eq!(life.cells(), universe.cells(), "rule {}", rule);
+        }
+    }
+
+    #[test]
+    fn test_counts() {
+        for rule in [
+            "R3,C0,M1,S34..58,B34..45,NM",
+            "R3,C0,M0,S2..5,B3..4,NN",
+            "R4,C0,M0,S2..3,B2..3,N+:T20,15",
+            "R2,C0,M1,S5..9,B4..6,NM:K12*,10",
+        ] {
+            let mut universe = Universe::new("1101\n0110\n1011\n0001");
+            universe.translate(6, 5);
+            universe.set_cell(0, 15, true);
+            universe.set_cell(15, 0, true);
+            universe.rule = Rule::parse(rule).unwrap();
+            let rule = universe.rule.larger_than_life().unwrap();
+            let topology = universe.rule.topology();
+            let area = Boundaries {
+                line_min: -4,
+                line_max: 19,
+                column_min: -3,
+                column_max: 20,
+            };
+            let mut expected = vec![];
+            for line in area.line_min..=area.line_max {
+                for column in area.column_min..=area.column_max {
+                    let mut count = 0;
+                    for lines in -rule.radius..=rule.radius {
+                        for columns in -rule.radius..=rule.radius {
+                            let inside = match rule.shape {
+                                Shape::Box => true,
+                                Shape::Diamond => lines.abs() + columns.abs() <= rule.radius,
+                                Shape::Cross => lines == 0 || columns == 0,
+                            };
+                            let itself = lines == 0 && columns == 0 && !rule.middle;
+                            let alive = topology
+                                .wrap(line + lines, column + columns)
+                                .is_some_and(|(line, column)| universe.state(line, column) == 1);
+                            if inside && !itself && alive {
+                                count += 1;
+                            }
+                        }
+                    }
+                    expected.push(count);
+                }
+            }
+            assert_eq!(
+                expected,
+                rule.counts(&universe, &area),
+                "rule {}",
+                universe.rule
+            );
+        }
+    }
+}
@@ -0,0 +1,447 @@
+use crate::universe::rule::Rule;
+use crate::universe::{Entity, ParseError, Universe};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::rc::Rc;
+
+pub const MACROCELL_HEADER: &str = "[M2]";
+
+/// Level of the 8x8 leaves written as rows of `.` and `*` in two-state patterns.
+const LEAF_LEVEL: u32 = 3;
+/// Level of the root covering the 32-bit coordinates.
+const MAX_LEVEL: u32 = 32;
+
+type Cell = (i64, i64, u8);
+
+/// A node read from a Macrocell file; children refer to earlier nodes, 0 being empty.
+pub(crate) enum Node {
+    /// Live cells of an 8x8 leaf.
+    Leaf(Vec<Cell>),
+    /// States of the four cells of a level 1 node, in multi-state patterns.
+    States([u8; 4]),
+    Inner {
+        level: u32,
+        children: [usize; 4],
+    },
+}
+
+fn level(nodes: &[Node], id: usize) -> Option<u32> {
+    match nodes.get(id)? {
+        Node::Leaf(_) => Some(LEAF_LEVEL),
+        Node::States(_) => Some(1),
+        Node::Inner { level, .. } => Some(*level),
+    }
+}
+
+fn parse_leaf(row: &str) -> std::result::Result<Node, ParseError> {
+    let mut cells = vec![];
+    let (mut line, mut column) = (0, 0);
+    for entry in row.chars() {
+        match entry {
+            '$' => {
+                line += 1;
+                column = 0;
+                continue;
+            }
+            '.' | '*' if line >= 8 || column >= 8 => {
+                return Err(ParseError::new(format!(
+                    "Macrocell leaf larger than 8x8: '{}'",
+                    row
+                )))
+            }
+            '.' => {}
+            '*' => cells.push((line, column, 1)),
+            _ => {
+                return Err(ParseError::new(format!(
+                    "unexpected '{}' in Macrocell leaf '{}'",
+                    entry, row
+                )))
+            }
+        }
+        column += 1;
+    }
+    Ok(Node::Leaf(cells))
+}
+
+fn parse_node(row: &str, nodes: &[Node], states: u16) -> std::result::Result<Node, ParseError> {
+    let invalid = || ParseError::new(format!("invalid Macrocell node '{}'", row));
+    let values = row
+        .split_whitespace()
+        .map(|value| value.parse::<usize>())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|_| invalid())?;
+    let (level, children) = match values.as_slice() {
+        [level, nw, ne, sw, se] => (*level as u32, [*nw, *ne, *sw, *se]),
+        _ => return Err(invalid()),
+    };
+    if level == 1 {
+        let mut cells = [0; 4];
+        for (cell, state) in cells.iter_mut().zip(children.iter()) {
+            *cell = u8::try_from(*state)
+                .ok()
+                .filter(|state| u16::from(*state) < states)
+                .ok_or_else(|| {
+                    ParseError::new(format!("invalid state {} in Macrocell node", state))
+                })?;
+        }
+        return Ok(Node::States(cells));
+    }
+    // children are earlier nodes of the level below, or empty
+    let valid = level >= 2
+        && children
+            .iter()
+            .all(|child| *child == 0 || level_of(nodes, *child) == Some(level - 1));
+    if !valid {
+        return Err(invalid());
+    }
+    Ok(Node::Inner { level, children })
+}
+
+fn level_of(nodes: &[Node], id: usize) -> Option<u32> {
+    id.checked_sub(1).and_then(|index| level(nodes, index))
+}
+
+fn children_of(nodes: &[Node], id: usize) -> [usize; 4] {
+    match id.checked_sub(1).and_then(|index| nodes.get(index)) {
+        Some(Node::Inner { children, .. }) => *children,
+        _ => [0; 4],
+    }
+}
+
+fn expand(nodes: &[Node], id: usize, line: i64, column: i64, cells: &mut Vec<Cell>) {
+    let node = match id.checked_sub(1).and_then(|index| nodes.get(index)) {
+        Some(node) => node,
+        None => return,
+    };
+    match node {
+        Node::Leaf(leaf) => cells.extend(leaf.iter().map(|(cell_line, cell_column, state)| {
+            (line + cell_line, column + cell_column, *state)
+        })),
+        Node::States(states) => {
+            let offsets = [(0, 0), (0, 1), (1, 0), (1, 1)];
+            for ((cell_line, cell_column), state) in offsets.iter().zip(states.iter()) {
+                if *state != 0 {
+                    cells.push((line + cell_line, column + cell_column, *state));
+                }
+            }
+        }
+        Node::Inner { level, children } => {
+            let half = 1 << (level - 1);
+            let corners = [(0, 0), (0, half), (half, 0), (half, half)];
+            for (child, (lines, columns)) in children.iter().zip(corners.iter()) {
+                expand(nodes, *child, line + lines, column + columns, cells);
+            }
+        }
+    }
+}
+
+struct Writer {
+    multi_state: bool,
+    rows: Vec<String>,
+    index: HashMap<String, usize>,
+}
+
+impl Writer {
+    fn add(&mut self, row: String) -> usize {
+        if let Some(id) = self.index.get(&row) {
+            return *id;
+        }
+        self.rows.push(row.clone());
+        self.index.insert(row, self.rows.len());
+        self.rows.len()
+    }
+
+    fn node(&mut self, cells: Vec<Cell>, level: u32) -> usize {
+        if cells.is_empty() {
+            return 0;
+        }
+        if level == 1 {
+            let mut states = [0; 4];
+            for (line, column, state) in cells {
+                states[(line * 2 + column) as usize] = state;
+            }
+            let [nw, ne, sw, se] = states;
+            return self.add(format!("1 {} {} {} {}", nw, ne, sw, se));
+        }
+        if level == LEAF_LEVEL && !self.multi_state {
+            let mut rows = vec![String::new(); 8];
+            for (line, column, _) in cells {
+                let row = &mut rows[line as usize];
+                row.push_str(&".".repeat(column as usize - row.len()));
+                row.push('*');
+            }
+            while rows.last().is_some_and(|row| row.is_empty()) {
+                rows.pop();
+            }
+            return self.add(rows.iter().map(|row| format!("{}$", row)).collect());
+        }
+        let half = 1 << (level - 1);
+        let mut quadrants = [vec![], vec![], vec![], vec![]];
+        for (line, column, state) in cells {
+            let quadrant = usize::from(line >= half) * 2 + usize::from(column >= half);
+            quadrants[quadrant].push((line % half, column % half, state));
+        }
+        let [nw, ne, sw, se] = quadrants.map(|quadrant| self.node(quadrant, level - 1));
+        self.add(format!("{} {} {} {} {}", level, nw, ne, sw, se))
+    }
+}
+
+/// A pattern in Golly's Macrocell (`.mc`) format, a quadtree where identical nodes are written
+/// once, so patterns too large for RLE stay small.
+pub struct Macrocell {
+    pub rule: Rule,
+    pub generation: u64,
+    nodes: Vec<Node>,
+    // 1-based index of the root, 0 when the pattern is empty
+    root: usize,
+}
+
+impl Macrocell {
+    pub fn parse(value: &str) -> std::result::Result<Self, ParseError> {
+        let mut rows = value.lines().map(|row| row.trim());
+        if !rows
+            .next()
+            .is_some_and(|header| header.starts_with(MACROCELL_HEADER))
+        {
+            return Err(ParseError::new(format!(
+                "Macrocell patterns start with '{}'",
+                MACROCELL_HEADER
+            )));
+        }
+        let mut rule = Rule::default();
+        let mut generation = 0;
+        let mut nodes = vec![];
+        for row in rows.filter(|row| !row.is_empty()) {
+            if let Some(value) = row.strip_prefix("#R") {
+                rule = Rule::parse(value.trim())?;
+            } else if let Some(value) = row.strip_prefix("#G") {
+                generation = value.trim().parse().map_err(|_| {
+                    ParseError::new(format!("invalid generation '{}'", value.trim()))
+                })?;
+            } else if row.starts_with('#') {
+                // names, comments and Golly settings
+            } else if row.starts_with(['.', '*', '$']) {
+                nodes.push(parse_leaf(row)?);
+            } else {
+                let states = rule.states().max(u16::from(rule.teams()) + 1);
+                nodes.push(parse_node(row, &nodes, states)?);
+            }
+        }
+        let mut root = nodes.len();
+        // larger roots are replaced by their center, as long as the cells fit in it
+        while let Some(Node::Inner { level, children }) = root.checked_sub(1).map(|id| &nodes[id]) {
+            if *level <= MAX_LEVEL {
+                break;
+            }
+            let level = *level - 1;
+            let [nw, ne, sw, se] = children.map(|child| children_of(&nodes, child));
+            let outside = [&nw[..3], &ne[..2], &ne[3..], &sw[..1], &sw[2..], &se[1..]];
+            if outside.iter().any(|ids| ids.iter().any(|id| *id != 0)) {
+                return Err(ParseError::new(format!(
+                    "Macrocell pattern with cells beyond {} cells from the center",
+                    1_u64 << (MAX_LEVEL - 1)
+                )));
+            }
+            nodes.push(Node::Inner {
+                level,
+                children: [nw[3], ne[2], sw[1], se[0]],
+            });
+            root = nodes.len();
+        }
+        Ok(Macrocell {
+            rule,
+            generation,
+            nodes,
+            root,
+        })
+    }
+
+    /// The node from its 1-based index, `None` for empty nodes.
+    pub(crate) fn node(&self, id: usize) -> Option<&Node> {
+        id.checked_sub(1).and_then(|index| self.nodes.get(index))
+    }
+
+    /// The 1-based index of the root and its level.
+    pub(crate) fn root(&self) -> (usize, u32) {
+        (self.root, level_of(&self.nodes, self.root).unwrap_or(1))
+    }
+
+    pub fn to_universe(&self) -> std::result::Result<Universe, ParseError> {
+        let mut universe = Universe::new("");
+        universe.rule = self.rule.clone();
+        universe.generation = self.generation;
+        let (root, level) = self.root();
+        let half = 1_i64 << (level - 1);
+        let mut cells = vec![];
+        expand(&self.nodes, root, -half, -half, &mut cells);
+        let teams = universe.rule.teams();
+        for (line, column, state) in cells {
+            let position = |value: i64| {
+                i32::try_from(value)
+                    .map_err(|_| ParseError::new("Macrocell pattern too large".to_string()))
+            };
+            // as in Golly, colored cells are stored as one state per team
+            let (state, team) = if teams > 1 {
+                (1, state - 1)
+            } else {
+                (state, 0)
+            };
+            universe.entities.insert(Rc::new(Entity {
+                line: position(line)?,
+                column: position(column)?,
+                state,
+                team,
+            }));
+        }
+        Ok(universe)
+    }
+}
+
+impl Universe {
+    pub fn from_macrocell(value: &str) -> std::result::Result<Self, ParseError> {
+        Macrocell::parse(value)?.to_universe()
+    }
+
+    pub fn to_macrocell(&self) -> String {
+        let mut result = format!("{}\n#R {}\n", MACROCELL_HEADER, self.rule);
+        if self.generation > 0 {
+            let _ = writeln!(result, "#G {}", self.generation);
+        }
+        let teams = self.rule.teams();
+        let cells = self
+            .entities
+            .iter()
+            .map(|entity| {
+                let state = if teams > 1 {
+                    entity.team + 1
+                } else {
+                    entity.state
+                };
+                (i64::from(entity.line), i64::from(entity.column), state)
+            })
+            .collect::<Vec<_>>();
+        // the smallest root centered on the origin holding all the cells
+        let extent = cells
+            .iter()
+            .map(|(line, column, _)| (-line).max(line + 1).max(-column).max(column + 1))
+            .max()
+            .unwrap_or(0);
+        let mut level = LEAF_LEVEL + 1;
+        while (1_i64 << (level - 1)) < extent {
+            level += 1;
+        }
+        let half = 1_i64 << (level - 1);
+        let mut writer = Writer {
+            multi_state: self.rule.states() > 2 || teams > 1,
+            rows: vec![],
+            index: HashMap::new(),
+        };
+        let cells = cells
+            .into_iter()
+            .map(|(line, column, state)| (line + half, column + half, state))
+            .collect();
+        if writer.node(cells, level) == 0 {
+            // an empty root, so the pattern is not mistaken for a truncated file
+            let empty = writer.add(format!("{} 0 0 0 0", level));
+            debug_assert_eq!(1, empty);
+        }
+        for row in writer.rows {
+            result.push_str(&row);
+            result.push('\n');
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::LifeEngine;
+    use crate::universe::format::Format;
+
+    #[test]
+    fn test_parse() {
+        // Golly's glider, in the south east quadrant of a 16x16 root
+        let glider = Universe::from_macrocell(
+            "[M2] (golly 4.2)\n#R B3/S23\n#G 12\n.*$..*$***$\n4 0 0 0 1\n",
+        )
+        .unwrap();
+        assert_eq!(12, glider.generation);
+        assert_eq!(
+            vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)],
+            glider
+                .cells()
+                .iter()
+                .map(|entity| (entity.line, entity.column))
+                .collect::<Vec<_>>()
+        );
+        let brain = Universe::from_macrocell("[M2]\n#R /2/3\n1 0 1 2 0\n2 1 0 0 1\n").unwrap();
+        assert_eq!(
+            vec![(-2, -1, 1), (-1, -2, 2), (0, 1, 1), (1, 0, 2)],
+            brain
+                .cells()
+                .iter()
+                .map(|entity| (entity.line, entity.column, entity.state))
+                .collect::<Vec<_>>()
+        );
+        for invalid in [
+            "x = 1, y = 1\no!",
+            "[M2]\n.*$x\n",
+            "[M2]\n*********$\n",
+            "[M2]\n$$$$$$$$*$\n",
+            "[M2]\n.*$\n4 0 0 0 2\n",
+            "[M2]\n.*$\n5 0 0 0 1\n",
+            "[M2]\n.*$\n4 0 0 1\n",
+            "[M2]\n1 0 1 2 0\n",
+            "[M2]\n#G -1\n",
+        ] {
+            assert!(Universe::from_macrocell(invalid).is_err(), "{}", invalid);
+        }
+
+        // a root larger than the coordinates, with the cells near its center
+        let mut large = "[M2]\n.*$..*$***$\n4 1 0 0 0\n".to_string();
+        for level in 5..=34 {
+            large.push_str(&format!("{} {} 0 0 0\n", level, level - 3));
+        }
+        large.push_str("35 0 0 0 32\n");
+        let glider = Universe::from_macrocell(&large).unwrap();
+        assert_eq!(Universe::new(".1\n..1\n111"), glider);
+        large.push_str("36 32 0 0 33\n");
+        assert!(Universe::from_macrocell(&large).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut r_pentomino = Universe::new(".11\n11\n.1");
+        r_pentomino.translate(-20, -30);
+        r_pentomino.step(5);
+        let macrocell = r_pentomino.to_macrocell();
+        assert!(macrocell.starts_with("[M2]\n#R B3/S23\n#G 5\n"));
+        let read = Universe::from_macrocell(&macrocell).unwrap();
+        assert_eq!(r_pentomino.cells(), read.cells());
+        assert_eq!(5, read.generation);
+        assert_eq!(Some(Format::Macrocell), Format::detect(&macrocell));
+
+        // a large empty area between two cells takes a few lines
+        let mut far = Universe::new("1");
+        far.set_cell(-1_000_000_000, 2_000_000_000, true);
+        let macrocell = far.to_macrocell();
+        assert!(macrocell.lines().count() < 70, "{}", macrocell);
+        assert_eq!(far, Universe::from_macrocell(&macrocell).unwrap());
+
+        for (pattern, rule) in [
+            ("12\n03", "345/2/4"),
+            ("11\n01", "QuadLife"),
+            ("", "B3/S23"),
+        ] {
+            let mut universe = Universe::new(pattern);
+            universe.set_rule(Rule::parse(rule).unwrap());
+            if rule == "QuadLife" {
+                universe.set_team(5, -7, 3);
+            }
+            let read = Universe::from_macrocell(&universe.to_macrocell()).unwrap();
+            assert_eq!(universe, read, "rule {}", rule);
+        }
+    }
+}
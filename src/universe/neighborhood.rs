@@ -0,0 +1,153 @@
+use crate::universe::{ParseError, CELL_SIZE};
+use std::fmt::{Display, Formatter, Result};
+
+/// The eight surrounding cells, in reading order.
+const MOORE: [(i32, i32); 8] = [
+    (-1, -1), // top_left
+    (-1, 0),  // top
+    (-1, 1),  // top_right
+    (0, -1),  // left
+    (0, 1),   // right
+    (1, -1),  // bottom_left
+    (1, 0),   // bottom
+    (1, 1),   // bottom_right
+];
+
+/// The cells counted as neighbors, written as a suffix of the rule counts, e.g. `B2/S34H`.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum Neighborhood {
+    /// The eight surrounding cells (no suffix).
+    Moore,
+    /// The four orthogonal cells (`V`).
+    VonNeumann,
+    /// Six cells of a hexagonal lattice, emulated by ignoring the top right and bottom left
+    /// corners (`H`).
+    Hexagonal,
+    /// Any subset of the Moore neighborhood (`N@` followed by two hex digits), one bit per
+    /// cell in reading order starting from the most significant: `N@5A` is von Neumann.
+    Custom(u8),
+}
+
+impl Neighborhood {
+    /// Splits the neighborhood suffix from rule counts such as `B2/S34H`.
+    pub fn split(counts: &str) -> std::result::Result<(&str, Self), ParseError> {
+        if let Some((counts, digits)) = counts.rsplit_once("N@") {
+            return match u8::from_str_radix(digits, 16) {
+                Ok(mask)
+                    if digits.len() == 2
+                        && digits.chars().all(|digit| digit.is_ascii_hexdigit()) =>
+                {
+                    Ok((counts, Neighborhood::Custom(mask)))
+                }
+                _ => Err(ParseError::new(format!(
+                    "invalid neighborhood mask 'N@{}'",
+                    digits
+                ))),
+            };
+        }
+        let neighborhood = match counts.chars().last().map(|last| last.to_ascii_uppercase()) {
+            Some('V') => Neighborhood::VonNeumann,
+            Some('H') => Neighborhood::Hexagonal,
+            _ => return Ok((counts, Neighborhood::Moore)),
+        };
+        Ok((&counts[..counts.len() - 1], neighborhood))
+    }
+
+    fn mask(&self) -> u8 {
+        match *self {
+            Neighborhood::Moore => 0xff,
+            Neighborhood::VonNeumann => 0x5a,
+            Neighborhood::Hexagonal => 0xdb,
+            Neighborhood::Custom(mask) => mask,
+        }
+    }
+
+    /// Positions of the neighbors relative to the cell, with their bit in a configuration of
+    /// live neighbors (see [`Rule::next_state`](crate::universe::rule::Rule::next_state)).
+    pub fn offsets(&self) -> impl Iterator<Item = (u8, &'static (i32, i32))> {
+        let mask = self.mask();
+        MOORE
+            .iter()
+            .enumerate()
+            .map(|(index, offset)| (0x80 >> index, offset))
+            .filter(move |(bit, _)| mask & bit != 0)
+    }
+
+    /// Number of neighbors, the highest count a rule can use.
+    pub fn size(&self) -> usize {
+        self.mask().count_ones() as usize
+    }
+
+    /// Horizontal shift of a line in pixels, so hexagonal cells are drawn with each line
+    /// offset by half a cell from the one above.
+    pub fn shift(&self, line: i32) -> i32 {
+        match self {
+            Neighborhood::Hexagonal => -line * CELL_SIZE / 2,
+            _ => 0,
+        }
+    }
+}
+
+impl Display for Neighborhood {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Neighborhood::Moore => Ok(()),
+            Neighborhood::VonNeumann => write!(f, "V"),
+            Neighborhood::Hexagonal => write!(f, "H"),
+            Neighborhood::Custom(mask) => write!(f, "N@{:02X}", mask),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        assert_eq!(
+            ("B2/S34", Neighborhood::Hexagonal),
+            Neighborhood::split("B2/S34H").unwrap()
+        );
+        assert_eq!(
+            ("B13/S012", Neighborhood::VonNeumann),
+            Neighborhood::split("B13/S012v").unwrap()
+        );
+        assert_eq!(
+            ("B3/S23", Neighborhood::Custom(0xe7)),
+            Neighborhood::split("B3/S23N@E7").unwrap()
+        );
+        assert_eq!(
+            ("B3/S23", Neighborhood::Moore),
+            Neighborhood::split("B3/S23").unwrap()
+        );
+        assert!(Neighborhood::split("B3/S23N@").is_err());
+        assert!(Neighborhood::split("B3/S23N@1FF").is_err());
+        assert!(Neighborhood::split("B3/S23N@G0").is_err());
+        assert!(Neighborhood::split("B3/S23N@+5").is_err());
+    }
+
+    #[test]
+    fn test_offsets() {
+        assert_eq!(8, Neighborhood::Moore.size());
+        assert_eq!(
+            vec![(-1, 0), (0, -1), (0, 1), (1, 0)],
+            Neighborhood::VonNeumann
+                .offsets()
+                .map(|(_, offset)| *offset)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![(-1, -1), (-1, 0), (0, -1), (0, 1), (1, 0), (1, 1)],
+            Neighborhood::Hexagonal
+                .offsets()
+                .map(|(_, offset)| *offset)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Neighborhood::VonNeumann.offsets().collect::<Vec<_>>(),
+            Neighborhood::Custom(0x5a).offsets().collect::<Vec<_>>()
+        );
+        assert_eq!(0, Neighborhood::Custom(0).size());
+    }
+}
@@ -0,0 +1,286 @@
+use crate::engine::LifeEngine;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter, Result};
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+/// Number of generations remembered by a [`PeriodDetector`], the longest period it finds.
+pub const PERIOD_LIMIT: usize = 4096;
+/// Cells kept at most by a [`PeriodDetector`]; the oldest generations of large patterns are
+/// forgotten first, so their periods are found over fewer generations.
+const CELL_LIMIT: usize = 1 << 22;
+
+/// How the cells evolve once they repeat.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum Behavior {
+    Empty,
+    StillLife,
+    Oscillator {
+        period: u64,
+    },
+    /// Repeats after `period` generations, moved by `dx` columns and `dy` lines.
+    Spaceship {
+        period: u64,
+        dx: i32,
+        dy: i32,
+    },
+}
+
+impl Display for Behavior {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Behavior::Empty => write!(f, "empty"),
+            Behavior::StillLife => write!(f, "still life"),
+            Behavior::Oscillator { period } => write!(f, "oscillator of period {}", period),
+            Behavior::Spaceship { period, dx, dy } => write!(
+                f,
+                "spaceship with period {} and displacement ({}, {})",
+                period, dx, dy
+            ),
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone)]
+struct Seen {
+    generation: u64,
+    // the top left corner of the cells, and the cells moved there
+    line: i32,
+    column: i32,
+    cells: Rc<Vec<(i32, i32, u8, u8)>>,
+    // the generation parity for block rules, whose blocks alternate
+    phase: u64,
+}
+
+/// Finds when the cells of an engine repeat, by hashing them at each generation and comparing
+/// the cells with the same hash.
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct PeriodDetector {
+    seen: HashMap<u64, Seen>,
+    order: VecDeque<(u64, u64)>,
+    // number of cells in `seen`
+    stored: usize,
+    behavior: Option<Behavior>,
+}
+
+impl PeriodDetector {
+    /// Records the current generation of the engine, which must follow the previous one.
+    pub fn observe(&mut self, engine: &dyn LifeEngine) -> Option<Behavior> {
+        let rule = engine.rule();
+        let generation = engine.generation();
+        if rule.stochastic().is_some() {
+            self.behavior = None;
+            return None;
+        }
+        // patterns too large to be kept once are not followed, nor even listed
+        if engine.population() > CELL_LIMIT {
+            self.reset();
+            return None;
+        }
+        let entities = engine.cells();
+        if entities.is_empty() {
+            self.behavior = Some(Behavior::Empty);
+            return self.behavior;
+        }
+        let (line, column) = match rule.topology().size() {
+            Some(_) => (0, 0),
+            None => (
+                entities[0].line,
+                entities
+                    .iter()
+                    .map(|entity| entity.column)
+                    .min()
+                    .unwrap_or(0),
+            ),
+        };
+        let cells = entities
+            .iter()
+            .map(|entity| {
+                (
+                    entity.line - line,
+                    entity.column - column,
+                    entity.state,
+                    entity.team,
+                )
+            })
+            .collect::<Vec<_>>();
+        // the blocks of block rules alternate, so the same cells only repeat in the same phase
+        let phase = if rule.block().is_some() {
+            generation % 2
+        } else {
+            0
+        };
+        let mut hasher = DefaultHasher::new();
+        phase.hash(&mut hasher);
+        cells.hash(&mut hasher);
+        let hash = hasher.finish();
+        self.behavior = match self.seen.get(&hash) {
+            // different cells with the same hash are not a repeat
+            Some(seen)
+                if seen.generation < generation && seen.phase == phase && *seen.cells == cells =>
+            {
+                let period = generation - seen.generation;
+                Some(match (column - seen.column, line - seen.line) {
+                    (0, 0) if period == 1 => Behavior::StillLife,
+                    (0, 0) => Behavior::Oscillator { period },
+                    (dx, dy) => Behavior::Spaceship { period, dx, dy },
+                })
+            }
+            _ => None,
+        };
+        self.stored += cells.len();
+        let seen = Seen {
+            generation,
+            line,
+            column,
+            cells: Rc::new(cells),
+            phase,
+        };
+        if let Some(previous) = self.seen.insert(hash, seen) {
+            self.stored -= previous.cells.len();
+        }
+        self.order.push_back((hash, generation));
+        while self.order.len() > PERIOD_LIMIT || self.stored > CELL_LIMIT {
+            let (hash, generation) = match self.order.pop_front() {
+                Some(oldest) => oldest,
+                None => break,
+            };
+            if self
+                .seen
+                .get(&hash)
+                .is_some_and(|seen| seen.generation == generation)
+            {
+                if let Some(seen) = self.seen.remove(&hash) {
+                    self.stored -= seen.cells.len();
+                }
+            }
+        }
+        self.behavior
+    }
+
+    /// The behavior found at the last observed generation, if the cells repeated.
+    pub fn behavior(&self) -> Option<Behavior> {
+        self.behavior
+    }
+
+    /// Forgets the observed generations, after the cells or the rule were changed.
+    pub fn reset(&mut self) {
+        *self = PeriodDetector::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::universe::rule::Rule;
+    use crate::universe::Universe;
+
+    fn behavior(mut universe: Universe, generations: u64) -> Option<Behavior> {
+        let mut detector = PeriodDetector::default();
+        detector.observe(&universe);
+        for _ in 0..generations {
+            universe.step(1);
+            detector.observe(&universe);
+        }
+        detector.behavior()
+    }
+
+    #[test]
+    fn test_still_life() {
+        assert_eq!(None, behavior(Universe::new("11\n11"), 0));
+        assert_eq!(
+            Some(Behavior::StillLife),
+            behavior(Universe::new("11\n11"), 1)
+        );
+        assert_eq!(Some(Behavior::Empty), behavior(Universe::new("1"), 1));
+        assert_eq!("still life", Behavior::StillLife.to_string());
+    }
+
+    #[test]
+    fn test_blinker() {
+        assert_eq!(None, behavior(Universe::new("111"), 1));
+        let blinker = behavior(Universe::new("111"), 2);
+        assert_eq!(Some(Behavior::Oscillator { period: 2 }), blinker);
+        assert_eq!("oscillator of period 2", blinker.unwrap().to_string());
+        // still found long after the first repeat
+        assert_eq!(blinker, behavior(Universe::new("111"), 11));
+    }
+
+    #[test]
+    fn test_pulsar() {
+        let pulsar = Universe::new(
+            r#"
+0011100011100
+0000000000000
+1000010100001
+1000010100001
+1000010100001
+0011100011100
+0000000000000
+0011100011100
+1000010100001
+1000010100001
+1000010100001
+0000000000000
+0011100011100
+        "#,
+        );
+        assert_eq!(None, behavior(pulsar.clone(), 2));
+        assert_eq!(
+            Some(Behavior::Oscillator { period: 3 }),
+            behavior(pulsar, 3)
+        );
+    }
+
+    #[test]
+    fn test_glider() {
+        let glider = Universe::new("010\n001\n111");
+        assert_eq!(None, behavior(glider.clone(), 3));
+        let behavior = behavior(glider, 4);
+        assert_eq!(
+            Some(Behavior::Spaceship {
+                period: 4,
+                dx: 1,
+                dy: 1
+            }),
+            behavior
+        );
+        assert_eq!(
+            "spaceship with period 4 and displacement (1, 1)",
+            behavior.unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_hash_collision() {
+        let mut block = Universe::new("11\n11");
+        let mut detector = PeriodDetector::default();
+        detector.observe(&block);
+        // other cells stored with the same hash
+        for seen in detector.seen.values_mut() {
+            seen.cells = Rc::new(vec![(0, 0, 1, 0)]);
+        }
+        detector.stored = 1;
+        block.step(1);
+        assert_eq!(None, detector.observe(&block));
+        block.step(1);
+        assert_eq!(Some(Behavior::StillLife), detector.observe(&block));
+        assert_eq!(4, detector.stored);
+    }
+
+    #[test]
+    fn test_finite_grid() {
+        // the glider comes back to the same place after crossing the torus
+        let mut glider = Universe::new("010\n001\n111");
+        glider.rule = Rule::parse("B3/S23:T8,8").unwrap();
+        assert_eq!(None, behavior(glider.clone(), 4));
+        assert_eq!(
+            Some(Behavior::Oscillator { period: 32 }),
+            behavior(glider, 32)
+        );
+        let mut stochastic = Universe::new("11\n11");
+        stochastic.rule = Rule::parse("B3/S2(0.5)3").unwrap();
+        assert_eq!(None, behavior(stochastic, 4));
+    }
+}
@@ -0,0 +1,153 @@
+use crate::universe::rule::Rule;
+use crate::universe::{Entity, ParseError, Universe};
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+impl Universe {
+    /// Reads a pattern in plaintext (`.cells`) format: `.` for dead and `O` for live cells,
+    /// with `!` comment lines.
+    pub fn from_plaintext(value: &str) -> std::result::Result<Self, ParseError> {
+        let mut entities = BTreeSet::new();
+        let rows = value
+            .lines()
+            .map(|row| row.trim_end())
+            .filter(|row| !row.starts_with('!'));
+        for (line, row) in rows.enumerate() {
+            for (column, entry) in row.chars().enumerate() {
+                match entry {
+                    '.' => {}
+                    'O' | '*' => {
+                        entities.insert(Rc::new(Entity {
+                            line: line as i32,
+                            column: column as i32,
+                            state: 1,
+                            team: 0,
+                        }));
+                    }
+                    _ => {
+                        return Err(ParseError::new(format!(
+                            "unexpected '{}' in plaintext pattern",
+                            entry
+                        )))
+                    }
+                }
+            }
+        }
+        let mut universe = Universe::new("");
+        universe.entities = entities;
+        Ok(universe)
+    }
+
+    /// Writes the pattern in plaintext format, which only holds live cells of Conway's Life.
+    pub fn to_plaintext(&self) -> std::result::Result<String, ParseError> {
+        self.check_live_cells("plaintext", true)?;
+        Ok(self.to_rows('.', 'O'))
+    }
+
+    /// Fails when some cells are not simply alive, as in multi-state or colored rules, or when
+    /// the rule is not Conway's Life and the format has no place for it.
+    pub(crate) fn check_live_cells(
+        &self,
+        format: &str,
+        conway: bool,
+    ) -> std::result::Result<(), ParseError> {
+        if conway && self.rule != Rule::conway() {
+            return Err(ParseError::new(format!(
+                "{} patterns cannot hold rule {}",
+                format, self.rule
+            )));
+        }
+        match self.entities.iter().find(|e| e.state != 1 || e.team != 0) {
+            Some(entity) => Err(ParseError::new(format!(
+                "{} patterns cannot hold the state {} of team {}",
+                format, entity.state, entity.team
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// The rows of the pattern from its top left corner, in a single pass over the cells.
+    pub(crate) fn to_rows(&self, dead: char, alive: char) -> String {
+        let mut result = String::new();
+        let first = match self.entities.iter().next() {
+            Some(first) => first,
+            None => return result,
+        };
+        let column_min = self.boundaries().column_min;
+        let mut line = first.line;
+        let mut row = String::new();
+        for entity in &self.entities {
+            while line < entity.line {
+                if row.is_empty() {
+                    row.push(dead);
+                }
+                result.push_str(&row);
+                result.push('\n');
+                row.clear();
+                line += 1;
+            }
+            // the characters are ASCII, so the length of the row is its number of columns
+            let column = (entity.column - column_min) as usize;
+            while row.len() < column {
+                row.push(dead);
+            }
+            row.push(alive);
+        }
+        result.push_str(&row);
+        result.push('\n');
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plaintext() {
+        let universe = Universe::from_plaintext(
+            r#"!Name: Glider
+!
+.O
+..O
+OOO
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Universe::new(
+                r#"
+010
+001
+111
+        "#
+            ),
+            universe
+        );
+        assert_eq!(".O\n..O\nOOO\n", universe.to_plaintext().unwrap());
+        assert!(Universe::from_plaintext(".O\n.x").is_err());
+    }
+
+    #[test]
+    fn test_plaintext_empty_rows() {
+        let universe = Universe::from_plaintext("OO\n\n.\n..OO\n").unwrap();
+        assert_eq!(4, universe.entities.len());
+        assert_eq!("OO\n.\n.\n..OO\n", universe.to_plaintext().unwrap());
+        assert_eq!(
+            universe,
+            Universe::from_plaintext(&universe.to_plaintext().unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_plaintext_unsupported() {
+        let mut universe = Universe::new("11\n11");
+        universe.rule = Rule::parse("B36/S23").unwrap();
+        assert!(universe.to_plaintext().is_err());
+        let mut universe = Universe::new("12\n11");
+        universe.rule = Rule::parse("/2/3").unwrap();
+        assert!(universe.to_plaintext().is_err());
+        universe.rule = Rule::conway();
+        assert!(universe.to_plaintext().is_err());
+    }
+}
@@ -0,0 +1,459 @@
+use crate::universe::rule::Rule;
+use crate::universe::{Boundaries, Entity, ParseError, Universe};
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter, Result};
+use std::rc::Rc;
+
+const MAX_LINE_LENGTH: usize = 70;
+/// Bounds the memory used by a pattern, whatever its run counts.
+const MAX_CELLS: usize = 1 << 22;
+
+/// A pattern in Run Length Encoded format, as used by Golly and the LifeWiki.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Rle {
+    pub name: Option<String>,
+    pub comments: Vec<String>,
+    pub universe: Universe,
+}
+
+impl Rle {
+    pub fn parse(value: &str) -> std::result::Result<Self, ParseError> {
+        let mut name = None;
+        let mut comments = vec![];
+        let mut rule = None;
+        let mut body = String::new();
+        for line in value.lines().map(|line| line.trim()) {
+            if rule.is_none() {
+                if let Some(comment) = line.strip_prefix('#') {
+                    let mut chars = comment.chars();
+                    let kind = chars.next();
+                    let text = chars.as_str();
+                    match kind {
+                        Some('N') => name = Some(text.trim().to_string()),
+                        Some('C' | 'c') => comments.push(text.trim().to_string()),
+                        _ => {}
+                    }
+                } else if line.starts_with('x') {
+                    rule = Some(parse_header(line)?);
+                } else if !line.is_empty() {
+                    return Err(ParseError::new("missing RLE header line".to_string()));
+                }
+            } else {
+                body.push_str(line);
+                if line.contains('!') {
+                    break;
+                }
+            }
+        }
+        let rule = rule.ok_or_else(|| ParseError::new("missing RLE header line".to_string()))?;
+        let mut entities = parse_body(&body)?;
+        if rule.teams() > 1 {
+            entities = entities
+                .into_iter()
+                .map(|entity| match entity.state {
+                    state if state <= rule.teams() => Ok(Rc::new(Entity {
+                        state: 1,
+                        team: state - 1,
+                        ..*entity
+                    })),
+                    state => Err(ParseError::new(format!(
+                        "invalid cell state {} for rule {}",
+                        state, rule
+                    ))),
+                })
+                .collect::<std::result::Result<_, _>>()?;
+        } else if let Some(entity) = entities
+            .iter()
+            .find(|entity| u16::from(entity.state) >= rule.states())
+        {
+            return Err(ParseError::new(format!(
+                "invalid cell state {} for rule {}",
+                entity.state, rule
+            )));
+        }
+        Ok(Rle {
+            name,
+            comments,
+            universe: Universe {
+                entities,
+                rule,
+                ..Universe::new("")
+            },
+        })
+    }
+}
+
+fn parse_header(line: &str) -> std::result::Result<Rule, ParseError> {
+    // the rule is always the last entry and may itself contain commas ("B3/S23:T80,60")
+    let (size, rule) = match line.find("rule") {
+        Some(index) => (&line[..index], Some(&line[index..])),
+        None => (line, None),
+    };
+    for entry in size.split(',').filter(|entry| !entry.trim().is_empty()) {
+        let (key, value) = entry
+            .split_once('=')
+            .ok_or_else(|| ParseError::new(format!("invalid RLE header '{}'", line)))?;
+        if key.trim() == "x" || key.trim() == "y" {
+            value.trim().parse::<u32>().map_err(|_| {
+                ParseError::new(format!("invalid pattern size in RLE header '{}'", line))
+            })?;
+        }
+    }
+    match rule.and_then(|rule| rule.split_once('=')) {
+        Some((_, value)) => Rule::parse(value),
+        None => Ok(Rule::default()),
+    }
+}
+
+fn parse_body(body: &str) -> std::result::Result<BTreeSet<Rc<Entity>>, ParseError> {
+    let mut entities = BTreeSet::new();
+    let (mut line, mut column) = (0i32, 0i32);
+    let mut count = String::new();
+    // multi-state cells above 24 are written as a prefix in 'p'..'y' followed by a letter
+    let mut prefix = None;
+    for entry in body.chars() {
+        if entry.is_ascii_digit() {
+            count.push(entry);
+            continue;
+        }
+        if entry.is_whitespace() {
+            continue;
+        }
+        if ('p'..='y').contains(&entry) && prefix.is_none() {
+            prefix = Some(entry);
+            continue;
+        }
+        let run = if count.is_empty() {
+            1
+        } else {
+            count
+                .parse::<i32>()
+                .map_err(|_| ParseError::new(format!("invalid run count '{}'", count)))?
+        };
+        count.clear();
+        let state = match (prefix.take(), entry) {
+            (None, 'b' | '.') => 0,
+            (None, 'o') => 1,
+            (prefix, 'A'..='X') => {
+                let high = prefix.map_or(0, |prefix| prefix as u32 - 'p' as u32 + 1);
+                let state = high * 24 + entry as u32 - 'A' as u32 + 1;
+                u8::try_from(state).map_err(|_| {
+                    ParseError::new(format!("invalid cell state {} in RLE pattern", state))
+                })?
+            }
+            (None, '$') => {
+                line = line.checked_add(run).ok_or_else(too_large)?;
+                column = 0;
+                continue;
+            }
+            (None, '!') => return Ok(entities),
+            (_, _) => {
+                return Err(ParseError::new(format!(
+                    "unexpected '{}' in RLE pattern",
+                    entry
+                )))
+            }
+        };
+        if state == 0 {
+            column = column.checked_add(run).ok_or_else(too_large)?;
+            continue;
+        }
+        if entities.len() + run as usize > MAX_CELLS {
+            return Err(ParseError::new(format!(
+                "RLE pattern has more than {} live cells",
+                MAX_CELLS
+            )));
+        }
+        column.checked_add(run).ok_or_else(too_large)?;
+        for _ in 0..run {
+            entities.insert(Rc::new(Entity {
+                line,
+                column,
+                state,
+                team: 0,
+            }));
+            column += 1;
+        }
+    }
+    Err(ParseError::new(
+        "RLE pattern is missing the terminating '!'".to_string(),
+    ))
+}
+
+fn too_large() -> ParseError {
+    ParseError::new("RLE pattern is too large".to_string())
+}
+
+fn run(count: i32, tag: &str) -> String {
+    if count > 1 {
+        format!("{}{}", count, tag)
+    } else {
+        tag.to_string()
+    }
+}
+
+fn tag(state: u8, states: u16) -> String {
+    match (state, states) {
+        (0, 2) => "b".to_string(),
+        (_, 2) => "o".to_string(),
+        (0, _) => ".".to_string(),
+        _ => {
+            let letter = char::from(b'A' + (state - 1) % 24);
+            match (state - 1) / 24 {
+                0 => letter.to_string(),
+                high => format!("{}{}", char::from(b'p' + high - 1), letter),
+            }
+        }
+    }
+}
+
+fn encode_body(universe: &Universe) -> Vec<String> {
+    let mut tokens = vec![];
+    if universe.entities.is_empty() {
+        tokens.push("!".to_string());
+        return tokens;
+    }
+    // as in Golly, colored cells are written as one state per team
+    let teams = universe.rule.teams();
+    let states = if teams > 1 {
+        u16::from(teams) + 1
+    } else {
+        universe.rule.states()
+    };
+    let value = |entity: &Entity| {
+        if teams > 1 {
+            entity.team + 1
+        } else {
+            entity.state
+        }
+    };
+    let Boundaries {
+        line_min,
+        column_min,
+        ..
+    } = universe.boundaries();
+    let (mut line, mut column) = (line_min, column_min);
+    // the current run of cells in the same state
+    let (mut state, mut length) = (0, 0);
+    for entity in &universe.entities {
+        if entity.line != line || entity.column != column + length || value(entity) != state {
+            if length > 0 {
+                tokens.push(run(length, &tag(state, states)));
+                column += length;
+                length = 0;
+            }
+            if entity.line != line {
+                tokens.push(run(entity.line - line, "$"));
+                line = entity.line;
+                column = column_min;
+            }
+            if entity.column != column {
+                tokens.push(run(entity.column - column, &tag(0, states)));
+                column = entity.column;
+            }
+            state = value(entity);
+        }
+        length += 1;
+    }
+    tokens.push(run(length, &tag(state, states)));
+    tokens.push("!".to_string());
+    tokens
+}
+
+impl Display for Rle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if let Some(name) = &self.name {
+            writeln!(f, "#N {}", name)?;
+        }
+        for comment in &self.comments {
+            writeln!(f, "#C {}", comment)?;
+        }
+        let (width, height) = if self.universe.entities.is_empty() {
+            (0, 0)
+        } else {
+            let boundaries = self.universe.boundaries();
+            (
+                boundaries.column_max - boundaries.column_min + 1,
+                boundaries.line_max - boundaries.line_min + 1,
+            )
+        };
+        writeln!(
+            f,
+            "x = {}, y = {}, rule = {}",
+            width, height, self.universe.rule
+        )?;
+        let mut line = String::new();
+        for token in encode_body(&self.universe) {
+            if line.len() + token.len() > MAX_LINE_LENGTH {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+impl Universe {
+    pub fn from_rle(value: &str) -> std::result::Result<Self, ParseError> {
+        Ok(Rle::parse(value)?.universe)
+    }
+
+    pub fn to_rle(&self) -> String {
+        Rle {
+            name: None,
+            comments: vec![],
+            universe: self.clone(),
+        }
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOSPER_GLIDER_GUN: &str = r#"#N Gosper glider gun
+#C This was the first gun discovered.
+#C As its name suggests, it was discovered by Bill Gosper.
+x = 36, y = 9, rule = B3/S23
+24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b
+obo$10bo5bo7bo$11bo3bo$12b2o!
+"#;
+
+    #[test]
+    fn test_parse_glider() {
+        let universe = Universe::from_rle("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+        assert_eq!(
+            Universe::new(
+                r#"
+010
+001
+111
+        "#
+            ),
+            universe
+        );
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let universe = Universe::from_rle("x = 5, y = 1, rule = 23/36\n5o!").unwrap();
+        assert_eq!("B36/S23", universe.rule.to_string());
+        assert_eq!(5, universe.entities.len());
+        assert!(Universe::from_rle("x = 1, y = 1, rule = B9/S23\no!").is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Universe::from_rle("bo$2bo$3o!").is_err());
+        assert!(Universe::from_rle("x = 3, y = 3\nbo$2bo$3o").is_err());
+        assert!(Universe::from_rle("x = 3, y = 3\nbo$2bz$3o!").is_err());
+        assert!(Universe::from_rle("x = a, y = 3\nbo$2bo$3o!").is_err());
+        assert!(Universe::from_rle("#Ñ\n#—\nbo$2bo$3o!").is_err());
+        assert!(Universe::from_rle("x = 3, y = 1\n.AB!").is_err());
+        assert!(Universe::from_rle("x = 3, y = 1, rule = /2/3\n.AC!").is_err());
+        assert!(Universe::from_rle("x = 0, y = 0\n999999999o!").is_err());
+        assert!(Universe::from_rle("x = 0, y = 0\n2000000000$2000000000$o!").is_err());
+    }
+
+    #[test]
+    fn test_gosper_glider_gun_round_trip() {
+        let rle = Rle::parse(GOSPER_GLIDER_GUN).unwrap();
+        assert_eq!(Some("Gosper glider gun".to_string()), rle.name);
+        assert_eq!(2, rle.comments.len());
+        assert_eq!(36, rle.universe.entities.len());
+        assert_eq!(GOSPER_GLIDER_GUN, rle.to_string());
+        assert_eq!(rle, Rle::parse(&rle.to_string()).unwrap());
+    }
+
+    #[test]
+    fn test_non_ascii_comments() {
+        let rle = Rle::parse("#N Ñandú\n#C año — 2024\n#Ñ\n#\nx = 1, y = 1\no!").unwrap();
+        assert_eq!(Some("Ñandú".to_string()), rle.name);
+        assert_eq!(vec!["año — 2024".to_string()], rle.comments);
+        assert_eq!(rle, Rle::parse(&rle.to_string()).unwrap());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut universe = Universe::new(
+            r#"
+0000000
+0110000
+0000000
+0000000
+0000011
+        "#,
+        );
+        universe.rule = Rule::parse("B36/S23").unwrap();
+        let rle = universe.to_rle();
+        assert_eq!("x = 6, y = 4, rule = B36/S23\n2o3$4b2o!\n", rle);
+        // the pattern is moved to the origin
+        let parsed = Universe::from_rle(&rle).unwrap();
+        assert_eq!(universe.to_rle(), parsed.to_rle());
+        assert_eq!(
+            Some(&Rc::new(Entity {
+                line: 0,
+                column: 0,
+                state: 1,
+                team: 0
+            })),
+            parsed.entities.iter().next()
+        );
+        assert_eq!(
+            "x = 0, y = 0, rule = B3/S23\n!\n",
+            Universe::new("").to_rle()
+        );
+    }
+
+    #[test]
+    fn test_multi_state() {
+        let mut universe = Universe::new(
+            r#"
+0110
+0220
+1001
+        "#,
+        );
+        universe.rule = Rule::parse("/2/3").unwrap();
+        let rle = universe.to_rle();
+        assert_eq!("x = 4, y = 3, rule = B2/S/C3\n.2A$.2B$A2.A!\n", rle);
+        assert_eq!(
+            universe.to_rle(),
+            Universe::from_rle(&rle).unwrap().to_rle()
+        );
+
+        let universe = Universe::from_rle("x = 3, y = 1, rule = B2/S/C255\npAyA2.!").unwrap();
+        assert_eq!(
+            vec![25, 241],
+            universe
+                .entities
+                .iter()
+                .map(|e| e.state)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("x = 2, y = 1, rule = B2/S/C255\npAyA!\n", universe.to_rle());
+        assert!(Universe::from_rle("x = 1, y = 1, rule = B2/S/C3\nyX!").is_err());
+        assert!(Universe::from_rle("x = 1, y = 1, rule = B2/S/C3\npo!").is_err());
+    }
+
+    #[test]
+    fn test_colored() {
+        let universe = Universe::from_rle("x = 4, y = 2, rule = QuadLife\nAB$.CD!").unwrap();
+        assert_eq!(
+            vec![(1, 0), (1, 1), (1, 2), (1, 3)],
+            universe
+                .entities
+                .iter()
+                .map(|e| (e.state, e.team))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "x = 3, y = 2, rule = QuadLife\nAB$.CD!\n",
+            universe.to_rle()
+        );
+        assert!(Universe::from_rle("x = 1, y = 1, rule = Immigration\nC!").is_err());
+    }
+}
@@ -0,0 +1,866 @@
+use crate::universe::block::BlockRule;
+use crate::universe::hensel;
+use crate::universe::ltl::LargerThanLife;
+use crate::universe::neighborhood::Neighborhood;
+use crate::universe::stochastic::Stochastic;
+use crate::universe::table::RuleTable;
+use crate::universe::topology::Topology;
+use crate::universe::ParseError;
+use rand::Rng;
+use std::fmt::{Display, Formatter, Result};
+use std::rc::Rc;
+use std::str::FromStr;
+
+/// Birth/survival rule, e.g. `B3/S23` for Conway's Life.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Rule {
+    family: Family,
+    states: u16,
+    teams: u8,
+    topology: Topology,
+}
+
+/// Variants of Conway's Life with colored cells, and their number of colors.
+const COLORED: [(&str, u8); 2] = [("Immigration", 2), ("QuadLife", 4)];
+
+/// Display colors of the teams.
+const TEAM_COLORS: [(u8, u8, u8); 4] =
+    [(240, 60, 60), (60, 140, 240), (80, 200, 90), (240, 200, 50)];
+
+#[derive(Eq, PartialEq, Debug, Clone)]
+enum Family {
+    LifeLike {
+        // indexed by the configuration of live neighbors
+        birth: Box<[bool; 256]>,
+        survival: Box<[bool; 256]>,
+        neighborhood: Neighborhood,
+    },
+    LargerThanLife(LargerThanLife),
+    Table(Rc<RuleTable>),
+    Elementary(u8),
+    Block(BlockRule),
+    Stochastic(Stochastic),
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::conway()
+    }
+}
+
+impl Rule {
+    pub fn conway() -> Self {
+        Rule::parse("B3/S23").expect("B3/S23 should be a valid rule")
+    }
+
+    pub fn parse(value: &str) -> std::result::Result<Self, ParseError> {
+        let value = value.trim();
+        let (counts, topology) = match value.split_once(':') {
+            Some((counts, suffix)) => (counts, Topology::parse(suffix)?),
+            None => (value, Topology::Plane),
+        };
+        let named = counts
+            .chars()
+            .next()
+            .is_some_and(|first| first.is_ascii_alphabetic())
+            && !counts.contains('/');
+        let elementary = counts
+            .strip_prefix(|prefix| prefix == 'W' || prefix == 'w')
+            .filter(|number| number.starts_with(|digit: char| digit.is_ascii_digit()));
+        let colored = COLORED
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(counts.trim()));
+        let (family, states) = if let Some(number) = elementary {
+            let number = number.parse::<u8>().map_err(|_| {
+                ParseError::new(format!("elementary rules go from W0 to W255: '{}'", value))
+            })?;
+            (Family::Elementary(number), 2)
+        } else if BlockRule::matches(counts) {
+            (Family::Block(BlockRule::parse(counts)?), 2)
+        } else if LargerThanLife::matches(counts) {
+            let (rule, states) = LargerThanLife::parse(counts)?;
+            (Family::LargerThanLife(rule), u16::from(states))
+        } else if let Some((_, teams)) = colored {
+            let (family, states) = parse_life_like("B3/S23", value)?;
+            return Ok(Rule {
+                family,
+                states,
+                teams: *teams,
+                topology,
+            });
+        } else if Stochastic::matches(counts) {
+            let rule = Stochastic::parse(counts)?;
+            if rule.is_certain() {
+                parse_life_like(&rule.to_string(), value)?
+            } else {
+                (Family::Stochastic(rule), 2)
+            }
+        } else if named {
+            let table = RuleTable::find(counts.trim())
+                .ok_or_else(|| ParseError::new(format!("unknown rule '{}'", value)))?;
+            let states = table.states();
+            (Family::Table(table), states)
+        } else {
+            parse_life_like(counts, value)?
+        };
+        let rule = Rule {
+            family,
+            states,
+            teams: 1,
+            topology,
+        };
+        if let (Family::Block(_), Some((width, height))) = (&rule.family, rule.topology.size()) {
+            let torus = matches!(rule.topology, Topology::Torus { .. });
+            if !torus || width % 2 != 0 || height % 2 != 0 {
+                return Err(ParseError::new(format!(
+                    "block rules need the plane or a torus of even width and height: '{}'",
+                    value
+                )));
+            }
+        }
+        if rule.births_from_nothing() && rule.topology == Topology::Plane {
+            return Err(ParseError::new(format!(
+                "B0 rules are not supported on an unbounded universe: '{}'",
+                value
+            )));
+        }
+        Ok(rule)
+    }
+
+    /// Neighbors of range 1 rules; Larger than Life rules count cells further away.
+    pub fn neighborhood(&self) -> &Neighborhood {
+        match &self.family {
+            Family::LifeLike { neighborhood, .. } => neighborhood,
+            Family::LargerThanLife(_) | Family::Elementary(_) | Family::Block(_) => {
+                &Neighborhood::Moore
+            }
+            Family::Table(table) => table.neighborhood(),
+            Family::Stochastic(rule) => rule.neighborhood(),
+        }
+    }
+
+    pub fn larger_than_life(&self) -> Option<&LargerThanLife> {
+        match &self.family {
+            Family::LargerThanLife(rule) => Some(rule),
+            _ => None,
+        }
+    }
+
+    /// The Wolfram code of elementary rules.
+    pub fn elementary(&self) -> Option<u8> {
+        match self.family {
+            Family::Elementary(number) => Some(number),
+            _ => None,
+        }
+    }
+
+    pub fn block(&self) -> Option<&BlockRule> {
+        match &self.family {
+            Family::Block(rule) => Some(rule),
+            _ => None,
+        }
+    }
+
+    pub fn stochastic(&self) -> Option<&Stochastic> {
+        match &self.family {
+            Family::Stochastic(rule) => Some(rule),
+            _ => None,
+        }
+    }
+
+    pub fn table(&self) -> Option<&RuleTable> {
+        match &self.family {
+            Family::Table(table) => Some(table),
+            _ => None,
+        }
+    }
+
+    /// Display color of a state, for rules that define one.
+    pub fn color(&self, state: u8) -> Option<(u8, u8, u8)> {
+        self.table().and_then(|table| table.color(state))
+    }
+
+    pub fn topology(&self) -> &Topology {
+        &self.topology
+    }
+
+    /// Number of cell states, 2 for plain life-like rules.
+    pub fn states(&self) -> u16 {
+        self.states
+    }
+
+    /// Number of colors of the live cells, 1 except for Immigration and QuadLife.
+    pub fn teams(&self) -> u8 {
+        self.teams
+    }
+
+    /// Display color of a team, for rules with colored cells.
+    pub fn team_color(&self, team: u8) -> Option<(u8, u8, u8)> {
+        if self.teams > 1 {
+            TEAM_COLORS.get(usize::from(team)).copied()
+        } else {
+            None
+        }
+    }
+
+    /// The team of a cell born from the parents: the most common one, else the first one missing.
+    pub fn newborn_team(&self, parents: &[u8]) -> u8 {
+        let mut counts = vec![0; usize::from(self.teams)];
+        for parent in parents {
+            counts[usize::from(*parent)] += 1;
+        }
+        let most = counts.iter().copied().max().unwrap_or(0);
+        let mut majority = (0..self.teams).filter(|team| counts[usize::from(*team)] == most);
+        match (majority.next(), majority.next()) {
+            (Some(team), None) => team,
+            (first, _) => (0..self.teams)
+                .find(|team| counts[usize::from(*team)] == 0)
+                .or(first)
+                .unwrap_or(0),
+        }
+    }
+
+    /// How far the neighbors of a cell can be.
+    pub fn radius(&self) -> i32 {
+        self.larger_than_life().map_or(1, |rule| rule.radius())
+    }
+
+    /// Whether only the number of live neighbors matters, not their arrangement.
+    pub fn is_totalistic(&self) -> bool {
+        match &self.family {
+            Family::LifeLike {
+                birth, survival, ..
+            } => is_totalistic(birth, survival),
+            Family::LargerThanLife(_) | Family::Stochastic(_) => true,
+            Family::Table(_) | Family::Elementary(_) | Family::Block(_) => false,
+        }
+    }
+
+    fn births_from_nothing(&self) -> bool {
+        match &self.family {
+            Family::LifeLike { birth, .. } => birth[0],
+            Family::LargerThanLife(rule) => rule.born(0),
+            Family::Table(table) => table.next_state(0, &vec![0; table.offsets().len()]) != 0,
+            Family::Elementary(number) => number & 1 != 0,
+            // strobing rules alternate between the cells and their complement on the plane
+            Family::Block(rule) => rule.next(0) != 0 && !rule.strobes(),
+            Family::Stochastic(rule) => rule.possible(false, 0),
+        }
+    }
+
+    fn transition(&self, state: u8, born: bool, survives: bool) -> u8 {
+        match state {
+            0 => born as u8,
+            1 if survives => 1,
+            // dying cells age until they reach the last state
+            _ if u16::from(state) + 1 < self.states => state + 1,
+            _ => 0,
+        }
+    }
+
+    /// The state of a cell in the next generation, given the configuration of its live
+    /// neighbors (see [`Neighborhood::offsets`]).
+    pub fn next_state(&self, state: u8, configuration: u8) -> u8 {
+        match &self.family {
+            Family::LifeLike {
+                birth, survival, ..
+            } => {
+                let configuration = configuration as usize;
+                self.transition(state, birth[configuration], survival[configuration])
+            }
+            Family::LargerThanLife(_) => {
+                self.next_state_from_count(state, configuration.count_ones())
+            }
+            Family::Table(table) => {
+                let neighbors = table
+                    .offsets()
+                    .iter()
+                    .map(|offset| {
+                        Neighborhood::Moore
+                            .offsets()
+                            .find(|(_, moore)| *moore == offset)
+                            .map_or(0, |(bit, _)| (configuration & bit != 0) as u8)
+                    })
+                    .collect::<Vec<_>>();
+                table.next_state(state, &neighbors)
+            }
+            // cells are set from the three cells above them, and never change afterwards
+            Family::Elementary(_) if state != 0 => state,
+            Family::Elementary(number) => number >> (configuration >> 5) & 1,
+            // blocks are updated as a whole by the Margolus engine
+            Family::Block(_) => state,
+            Family::Stochastic(rule) => {
+                let count = configuration.count_ones();
+                self.transition(state, rule.likely(false, count), rule.likely(true, count))
+            }
+        }
+    }
+
+    /// Same as [`next_state`](Rule::next_state), drawing the outcome of stochastic rules from
+    /// the random numbers instead of taking the most likely one.
+    pub fn next_state_with(&self, state: u8, configuration: u8, random: &mut impl Rng) -> u8 {
+        match &self.family {
+            Family::Stochastic(rule) => {
+                let alive = rule.next(state == 1, configuration.count_ones(), random);
+                self.transition(state, alive, alive)
+            }
+            _ => self.next_state(state, configuration),
+        }
+    }
+
+    /// The state of a cell in the next generation, given its number of live neighbors.
+    pub fn next_state_from_count(&self, state: u8, count: u32) -> u8 {
+        match &self.family {
+            Family::LifeLike { .. }
+            | Family::Table(_)
+            | Family::Elementary(_)
+            | Family::Block(_)
+            | Family::Stochastic(_) => self.next_state(state, with_count(count as usize) as u8),
+            Family::LargerThanLife(rule) => {
+                self.transition(state, rule.born(count), rule.survives(count))
+            }
+        }
+    }
+
+    /// Whether a cell with the given number of live neighbors is alive in the next generation,
+    /// for totalistic rules.
+    pub fn next(&self, alive: bool, neighbors: usize) -> bool {
+        self.next_state_from_count(alive as u8, neighbors as u32) == 1
+    }
+}
+
+fn parse_life_like(counts: &str, value: &str) -> std::result::Result<(Family, u16), ParseError> {
+    let (counts, neighborhood) = Neighborhood::split(counts)?;
+    let parts: Vec<&str> = counts.split('/').collect();
+    let prefixed = parts
+        .iter()
+        .all(|part| part.starts_with(|prefix: char| "BSCbsc".contains(prefix)));
+    let (mut birth, mut survival, mut states) = (None, None, None);
+    if prefixed {
+        for part in &parts {
+            match part
+                .chars()
+                .next()
+                .map(|prefix| prefix.to_ascii_uppercase())
+            {
+                Some('B') => birth = Some(&part[1..]),
+                Some('S') => survival = Some(&part[1..]),
+                _ => states = Some(&part[1..]),
+            }
+        }
+    } else if parts.len() == 2 || parts.len() == 3 {
+        // legacy notation lists survival first: "23/3", or "345/2/4" for Generations
+        survival = Some(parts[0]);
+        birth = Some(parts[1]);
+        states = parts.get(2).copied();
+    }
+    let (birth, survival) = match (birth, survival) {
+        (Some(birth), Some(survival)) => (birth, survival),
+        _ => return Err(ParseError::new(format!("invalid rule '{}'", value))),
+    };
+    let states = match states {
+        Some(states) => match states.parse::<u8>() {
+            Ok(states) if states >= 2 => u16::from(states),
+            _ => {
+                return Err(ParseError::new(format!(
+                    "invalid number of states '{}' in rule '{}'",
+                    states, value
+                )))
+            }
+        },
+        None => 2,
+    };
+    let birth = parse_counts(birth, value)?;
+    let survival = parse_counts(survival, value)?;
+    let size = neighborhood.size();
+    if (0..256usize).any(|configuration| {
+        (birth[configuration] || survival[configuration])
+            && configuration.count_ones() as usize > size
+    }) {
+        return Err(ParseError::new(format!(
+            "neighbor counts above {} are not possible in rule '{}'",
+            size, value
+        )));
+    }
+    if neighborhood != Neighborhood::Moore && !is_totalistic(&birth, &survival) {
+        return Err(ParseError::new(format!(
+            "Hensel letters need the Moore neighborhood: '{}'",
+            value
+        )));
+    }
+    Ok((
+        Family::LifeLike {
+            birth: Box::new(birth),
+            survival: Box::new(survival),
+            neighborhood,
+        },
+        states,
+    ))
+}
+
+fn is_totalistic(birth: &[bool; 256], survival: &[bool; 256]) -> bool {
+    (0..256usize).all(|configuration| {
+        let first = with_count(configuration.count_ones() as usize);
+        birth[configuration] == birth[first] && survival[configuration] == survival[first]
+    })
+}
+
+fn with_count(count: usize) -> usize {
+    (0xff00 >> count) & 0xff
+}
+
+fn parse_counts(value: &str, rule: &str) -> std::result::Result<[bool; 256], ParseError> {
+    let mut table = [false; 256];
+    let mut entries = value.chars().peekable();
+    while let Some(entry) = entries.next() {
+        let count = match entry.to_digit(10) {
+            Some(digit) if digit < 9 => digit as usize,
+            _ => {
+                return Err(ParseError::new(format!(
+                    "invalid neighbor count '{}' in rule '{}'",
+                    entry, rule
+                )))
+            }
+        };
+        let negated = entries.next_if_eq(&'-').is_some();
+        let mut letters = String::new();
+        while let Some(letter) = entries.next_if(|letter| letter.is_ascii_lowercase()) {
+            if !hensel::letters(count).contains(letter) {
+                return Err(ParseError::new(format!(
+                    "invalid letter '{}' for {} neighbors in rule '{}'",
+                    letter, count, rule
+                )));
+            }
+            letters.push(letter);
+        }
+        if letters.is_empty() {
+            if negated {
+                return Err(ParseError::new(format!(
+                    "missing letters after '{}-' in rule '{}'",
+                    count, rule
+                )));
+            }
+            for (configuration, enabled) in table.iter_mut().enumerate() {
+                if configuration.count_ones() as usize == count {
+                    *enabled = true;
+                }
+            }
+            continue;
+        }
+        for letter in hensel::letters(count).chars() {
+            if letters.contains(letter) != negated {
+                for configuration in hensel::configurations(count, letter).unwrap_or_default() {
+                    table[configuration as usize] = true;
+                }
+            }
+        }
+    }
+    Ok(table)
+}
+
+fn format_counts(table: &[bool; 256]) -> String {
+    let mut result = String::new();
+    for count in 0..9 {
+        let letters = hensel::letters(count);
+        let enabled = letters
+            .chars()
+            .filter(|letter| {
+                hensel::configurations(count, *letter)
+                    .unwrap_or_default()
+                    .iter()
+                    .all(|configuration| table[*configuration as usize])
+            })
+            .collect::<String>();
+        if enabled.len() == letters.len() {
+            if table[with_count(count)] {
+                result.push_str(&count.to_string());
+            }
+        } else if !enabled.is_empty() {
+            let disabled = letters
+                .chars()
+                .filter(|letter| !enabled.contains(*letter))
+                .collect::<String>();
+            if disabled.len() < enabled.len() {
+                result.push_str(&format!("{}-{}", count, disabled));
+            } else {
+                result.push_str(&format!("{}{}", count, enabled));
+            }
+        }
+    }
+    result
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match &self.family {
+            Family::LifeLike { .. } if self.teams > 1 => {
+                let (name, _) = COLORED
+                    .iter()
+                    .find(|(_, teams)| *teams == self.teams)
+                    .expect("colored rules have a name");
+                write!(f, "{}", name)?;
+            }
+            Family::LifeLike {
+                birth,
+                survival,
+                neighborhood,
+            } => {
+                write!(f, "B{}/S{}", format_counts(birth), format_counts(survival))?;
+                if self.states > 2 {
+                    write!(f, "/C{}", self.states)?;
+                }
+                write!(f, "{}", neighborhood)?;
+            }
+            Family::LargerThanLife(rule) => write!(f, "{}", rule.format(self.states))?,
+            Family::Table(table) => write!(f, "{}", table.name())?,
+            Family::Elementary(number) => write!(f, "W{}", number)?,
+            Family::Block(rule) => write!(f, "{}", rule)?,
+            Family::Stochastic(rule) => write!(f, "{}", rule)?,
+        }
+        write!(f, "{}", self.topology)
+    }
+}
+
+impl FromStr for Rule {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        Rule::parse(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::LifeEngine;
+    use crate::universe::{Entity, Universe};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("B3/S23", Rule::parse("B3/S23").unwrap().to_string());
+        assert_eq!("B3/S23", Rule::parse("b3/s23").unwrap().to_string());
+        assert_eq!("B3/S23", Rule::parse("S23/B3").unwrap().to_string());
+        assert_eq!("B3/S23", Rule::parse("23/3").unwrap().to_string());
+        assert_eq!("B36/S23", Rule::parse("23/36").unwrap().to_string());
+        assert_eq!(
+            "B3678/S34678",
+            Rule::parse("B3678/S34678").unwrap().to_string()
+        );
+        assert_eq!("B2/S", Rule::parse("B2/S").unwrap().to_string());
+        assert_eq!(
+            "B3/S012345678",
+            Rule::parse("B3/S012345678").unwrap().to_string()
+        );
+        assert_eq!(Rule::conway(), Rule::default());
+        assert!(Rule::parse("B3S23").is_err());
+        assert!(Rule::parse("B39/S23").is_err());
+        assert!(Rule::parse("Bx/S23").is_err());
+        assert!(Rule::parse("B03/S23").is_err());
+        assert_eq!(
+            "B3/S23:T80,60",
+            Rule::parse("B3/S23:T80,60").unwrap().to_string()
+        );
+        assert_eq!(
+            "B3/S23:K80*,60",
+            Rule::parse("23/3:k80*,60").unwrap().to_string()
+        );
+        assert_eq!(
+            "B0123478/S34678:P20,20",
+            Rule::parse("B0123478/S34678:P20,20").unwrap().to_string()
+        );
+        assert!(Rule::parse("B3/S23:T80").is_err());
+        assert!(Rule::parse("B3/S23/C1").is_err());
+        assert!(Rule::parse("B3/C3").is_err());
+    }
+
+    #[test]
+    fn test_parse_generations() {
+        assert_eq!("B2/S/C3", Rule::parse("/2/3").unwrap().to_string());
+        assert_eq!("B2/S/C3", Rule::parse("B2/S/C3").unwrap().to_string());
+        assert_eq!("B2/S345/C4", Rule::parse("345/2/4").unwrap().to_string());
+        assert_eq!("B2/S345/C4", Rule::parse("S345/B2/C4").unwrap().to_string());
+        assert_eq!(
+            "B2/S345/C4:T40,40",
+            Rule::parse("345/2/4:T40,40").unwrap().to_string()
+        );
+        assert_eq!(2, Rule::parse("B3/S23/C2").unwrap().states());
+        assert_eq!(4, Rule::parse("345/2/4").unwrap().states());
+    }
+
+    #[test]
+    fn test_parse_neighborhood() {
+        assert_eq!("B2/S34H", Rule::parse("B2/S34H").unwrap().to_string());
+        assert_eq!("B2/S34H", Rule::parse("34/2h").unwrap().to_string());
+        assert_eq!(
+            "B13/S012V:T20,20",
+            Rule::parse("B13/S012V:T20,20").unwrap().to_string()
+        );
+        assert_eq!("B2/S/C3N@5A", Rule::parse("/2/3N@5a").unwrap().to_string());
+        assert_eq!(
+            &Neighborhood::Hexagonal,
+            Rule::parse("B2/S34H").unwrap().neighborhood()
+        );
+        assert!(Rule::parse("B5/S23V").is_err());
+        assert!(Rule::parse("B7/S23H").is_err());
+        assert!(Rule::parse("B3/S23N@03").is_err());
+        assert!(Rule::parse("B3/S23X").is_err());
+    }
+
+    #[test]
+    fn test_parse_hensel() {
+        for rule in [
+            "B2-a/S12",
+            "B3/S2-i34q",
+            "B2ce3/S",
+            "B2n3/S23-q",
+            "B35y/S1e2-a7c",
+        ] {
+            assert_eq!(rule, Rule::parse(rule).unwrap().to_string());
+        }
+        // letters are written in the canonical order, and all letters are just the count
+        assert_eq!("B2ce/S", Rule::parse("B2ec/S").unwrap().to_string());
+        assert_eq!(
+            "B3/S23",
+            Rule::parse("B3cekainqjry/S2ceaikn3").unwrap().to_string()
+        );
+        assert_eq!("B2-a/S1", Rule::parse("1/2ceikn").unwrap().to_string());
+        assert!(Rule::parse("B2-a/S12").unwrap().next_state(0, 0x50) == 1);
+        assert!(!Rule::parse("B2-a/S12").unwrap().is_totalistic());
+        assert!(Rule::parse("B36/S23").unwrap().is_totalistic());
+        assert!(Rule::parse("B2x/S").is_err());
+        assert!(Rule::parse("B1a/S").is_err());
+        assert!(Rule::parse("B0c/S").is_err());
+        assert!(Rule::parse("B2-/S").is_err());
+        assert!(Rule::parse("B2a/S3V").is_err());
+    }
+
+    #[test]
+    fn test_hensel_arrangements() {
+        let rule = Rule::parse("B2e/S").unwrap();
+        // two neighbors at a right angle
+        let mut universe = Universe::new("01\n10");
+        universe.rule = rule.clone();
+        universe.tick();
+        assert_eq!(1, universe.state(0, 0));
+        // two opposite neighbors
+        let mut universe = Universe::new("010\n000\n010");
+        universe.rule = rule;
+        universe.tick();
+        assert_eq!(0, universe.state(1, 1));
+    }
+
+    #[test]
+    fn test_tlife_blinker() {
+        // the middle cell of a blinker has two opposite neighbors, excluded by S2-i
+        let mut universe = Universe::new("111");
+        universe.rule = Rule::parse("B3/S2-i34q").unwrap();
+        universe.tick();
+        assert_eq!(
+            vec![(-1, 1), (1, 1)],
+            universe
+                .entities
+                .iter()
+                .map(|e| (e.line, e.column))
+                .collect::<Vec<_>>()
+        );
+        universe.tick();
+        assert!(universe.entities.is_empty());
+    }
+
+    #[test]
+    fn test_isotropy() {
+        let mut universe = Universe::new("0110\n1100\n0101\n0011");
+        universe.rule = Rule::parse("B2-a3/S12-k3e").unwrap();
+        let mut transposed = universe.clone();
+        transposed.entities = universe
+            .entities
+            .iter()
+            .map(|e| {
+                Rc::new(Entity {
+                    line: e.column,
+                    column: e.line,
+                    ..**e
+                })
+            })
+            .collect();
+        for _ in 0..20 {
+            universe.tick();
+            transposed.tick();
+        }
+        assert!(!universe.entities.is_empty());
+        let mut expected = transposed
+            .entities
+            .iter()
+            .map(|e| (e.column, e.line))
+            .collect::<Vec<_>>();
+        expected.sort_unstable();
+        assert_eq!(
+            expected,
+            universe
+                .entities
+                .iter()
+                .map(|e| (e.line, e.column))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_neighborhoods() {
+        for (rule, expected) in [
+            ("B1/SV", vec![(-1, 0), (0, -1), (0, 1), (1, 0)]),
+            (
+                "B1/SH",
+                vec![(-1, -1), (-1, 0), (0, -1), (0, 1), (1, 0), (1, 1)],
+            ),
+            ("B1/SN@81", vec![(-1, -1), (1, 1)]),
+        ] {
+            let mut universe = Universe::new("1");
+            universe.rule = Rule::parse(rule).unwrap();
+            universe.tick();
+            assert_eq!(
+                expected,
+                universe
+                    .entities
+                    .iter()
+                    .map(|e| (e.line, e.column))
+                    .collect::<Vec<_>>(),
+                "rule {}",
+                rule
+            );
+        }
+    }
+
+    #[test]
+    fn test_brians_brain() {
+        let brain = Rule::parse("/2/3").unwrap();
+        let mut universe = Universe::new("0110");
+        universe.rule = brain.clone();
+        universe.tick();
+        // the live cells start dying, and the cells next to both of them are born
+        let mut expected = Universe::new(
+            r#"
+0110
+0220
+0110
+        "#,
+        );
+        expected.rule = brain.clone();
+        expected.translate(-1, 0);
+        assert_eq!(expected, universe);
+        universe.tick();
+        let mut expected = Universe::new(
+            r#"
+0110
+0220
+1001
+0220
+0110
+        "#,
+        );
+        expected.rule = brain;
+        expected.translate(-2, 0);
+        assert_eq!(expected, universe);
+    }
+
+    #[test]
+    fn test_seeds() {
+        let mut universe = Universe::new("11");
+        universe.rule = Rule::parse("B2/S").unwrap();
+        universe.tick();
+        assert_eq!(
+            vec![(-1, 0), (-1, 1), (1, 0), (1, 1)],
+            universe
+                .entities
+                .iter()
+                .map(|e| (e.line, e.column))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_highlife_replicator() {
+        let replicator = r#"
+00111
+01001
+10001
+10010
+11100
+        "#;
+        let mut universe = Universe::new(replicator);
+        universe.rule = Rule::parse("B36/S23").unwrap();
+        for _ in 0..12 {
+            universe.tick();
+        }
+        let original = Universe::new(replicator);
+        assert_eq!(original.entities.len() * 2, universe.entities.len());
+        for offset in [-2, 2] {
+            for entity in &original.entities {
+                assert!(universe.entities.contains(&Entity {
+                    line: entity.line + offset,
+                    column: entity.column + offset,
+                    state: 1,
+                    team: 0,
+                }));
+            }
+        }
+
+        // the same pattern does not replicate under Conway's rules
+        let mut universe = Universe::new(replicator);
+        for _ in 0..12 {
+            universe.tick();
+        }
+        assert_ne!(original.entities.len() * 2, universe.entities.len());
+    }
+
+    #[test]
+    fn test_colored() {
+        assert_eq!(
+            "Immigration",
+            Rule::parse("immigration").unwrap().to_string()
+        );
+        let rule = Rule::parse("QuadLife:T20,20").unwrap();
+        assert_eq!("QuadLife:T20,20", rule.to_string());
+        assert_eq!(4, rule.teams());
+        assert_eq!(2, rule.states());
+        assert_eq!(1, Rule::conway().teams());
+        assert_eq!(None, Rule::conway().team_color(0));
+        assert_eq!(1, rule.newborn_team(&[1, 3, 1]));
+        assert_eq!(3, rule.newborn_team(&[0, 1, 2]));
+        assert_eq!(0, rule.newborn_team(&[3, 1, 2]));
+        assert_eq!(2, rule.newborn_team(&[0, 1, 3, 2, 2]));
+    }
+
+    #[test]
+    fn test_colored_blinker() {
+        let teams = |universe: &Universe| {
+            universe
+                .entities
+                .iter()
+                .map(|entity| (entity.line, entity.column, entity.team))
+                .collect::<Vec<_>>()
+        };
+        for (rule, parents, newborn) in [("Immigration", [0, 1, 0], 0), ("QuadLife", [0, 1, 2], 3)]
+        {
+            let mut universe = Universe::new("");
+            universe.rule = Rule::parse(rule).unwrap();
+            for (column, team) in parents.iter().enumerate() {
+                universe.set_team(1, column as i32, *team);
+            }
+            universe.tick();
+            // the middle cell survives, the others are born from the three cells of the row
+            assert_eq!(
+                vec![(0, 1, newborn), (1, 1, 1), (2, 1, newborn)],
+                teams(&universe),
+                "rule {}",
+                rule
+            );
+            universe.tick();
+            assert_eq!(
+                vec![(1, 0, newborn), (1, 1, 1), (1, 2, newborn)],
+                teams(&universe),
+                "rule {}",
+                rule
+            );
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use crate::universe::ParseError;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt::{Display, Formatter, Result};
+use std::str::FromStr;
+
+/// Symmetries of a soup, named as in apgsearch.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum Symmetry {
+    /// No symmetry.
+    C1,
+    /// Unchanged by a half turn.
+    C2,
+    /// Unchanged by a quarter turn; the soup is square.
+    C4,
+    /// Mirrored left to right.
+    D2,
+    /// Mirrored left to right and top to bottom.
+    D4,
+    /// Unchanged by all the rotations and reflections of the square; the soup is square.
+    D8,
+}
+
+pub const SYMMETRIES: [Symmetry; 6] = [
+    Symmetry::C1,
+    Symmetry::C2,
+    Symmetry::C4,
+    Symmetry::D2,
+    Symmetry::D4,
+    Symmetry::D8,
+];
+
+impl Symmetry {
+    fn is_square(&self) -> bool {
+        matches!(self, Symmetry::C4 | Symmetry::D8)
+    }
+
+    fn images(&self, line: i32, column: i32, width: i32, height: i32) -> Vec<(i32, i32)> {
+        let (last_line, last_column) = (height - 1, width - 1);
+        let half_turn = (last_line - line, last_column - column);
+        let mirror = (line, last_column - column);
+        let quarter_turns = [(column, last_line - line), (last_column - column, line)];
+        let mut images = vec![(line, column)];
+        match self {
+            Symmetry::C1 => {}
+            Symmetry::C2 => images.push(half_turn),
+            Symmetry::C4 => {
+                images.push(half_turn);
+                images.extend(quarter_turns);
+            }
+            Symmetry::D2 => images.push(mirror),
+            Symmetry::D4 => images.extend([half_turn, mirror, (last_line - line, column)]),
+            Symmetry::D8 => {
+                images.extend([half_turn, mirror, (last_line - line, column)]);
+                images.extend(quarter_turns);
+                images.extend([(column, line), (last_column - column, last_line - line)]);
+            }
+        }
+        images
+    }
+}
+
+impl Display for Symmetry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for Symmetry {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        SYMMETRIES
+            .iter()
+            .find(|symmetry| symmetry.to_string().eq_ignore_ascii_case(value.trim()))
+            .copied()
+            .ok_or_else(|| ParseError::new(format!("unknown symmetry '{}'", value)))
+    }
+}
+
+/// Settings of a random pattern; the same settings always give the same cells.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Soup {
+    pub seed: u64,
+    /// Percentage of live cells.
+    pub density: u8,
+    /// Side of the square region filled with cells, or the whole area when `None`.
+    pub size: Option<i32>,
+    pub symmetry: Symmetry,
+}
+
+impl Default for Soup {
+    fn default() -> Self {
+        Soup {
+            seed: 0,
+            density: 10,
+            size: None,
+            symmetry: Symmetry::C1,
+        }
+    }
+}
+
+impl Soup {
+    /// The live cells, as lines and columns in a `width` x `height` area, the region of
+    /// the soup being centered in it.
+    pub fn cells(&self, width: i32, height: i32) -> Vec<(i32, i32)> {
+        let (mut region_width, mut region_height) = match self.size {
+            Some(size) => (size.min(width), size.min(height)),
+            None => (width, height),
+        };
+        if self.symmetry.is_square() {
+            region_width = region_width.min(region_height);
+            region_height = region_width;
+        }
+        let top = (height - region_height) / 2;
+        let left = (width - region_width) / 2;
+        let mut random = StdRng::seed_from_u64(self.seed);
+        let mut cells = vec![];
+        for line in 0..region_height {
+            for column in 0..region_width {
+                let images = self
+                    .symmetry
+                    .images(line, column, region_width, region_height);
+                // one draw for each set of images, made at the first of them
+                if images.iter().min() != Some(&(line, column)) {
+                    continue;
+                }
+                if random.gen_range(0..100) < self.density {
+                    cells.extend(images);
+                }
+            }
+        }
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+            .into_iter()
+            .map(|(line, column)| (line + top, column + left))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_reproducible() {
+        let soup = Soup {
+            seed: 42,
+            density: 50,
+            size: Some(16),
+            symmetry: Symmetry::C1,
+        };
+        let cells = soup.cells(40, 30);
+        assert_eq!(cells, soup.cells(40, 30));
+        assert_ne!(cells, Soup { seed: 43, ..soup }.cells(40, 30));
+        // about half of the 16 x 16 region, centered in the area
+        assert!((96..160).contains(&cells.len()), "{} cells", cells.len());
+        assert!(cells
+            .iter()
+            .all(|(line, column)| (7..23).contains(line) && (12..28).contains(column)));
+        assert!(Soup {
+            density: 0,
+            ..Soup::default()
+        }
+        .cells(20, 20)
+        .is_empty());
+        assert_eq!(
+            400,
+            Soup {
+                density: 100,
+                ..Soup::default()
+            }
+            .cells(20, 20)
+            .len()
+        );
+    }
+
+    #[test]
+    fn test_symmetries() {
+        for symmetry in SYMMETRIES {
+            assert_eq!(symmetry, symmetry.to_string().parse().unwrap());
+            for (width, height) in [(16, 16), (15, 12)] {
+                let soup = Soup {
+                    seed: 3,
+                    density: 30,
+                    size: None,
+                    symmetry,
+                };
+                let cells = soup
+                    .cells(width, height)
+                    .into_iter()
+                    .collect::<BTreeSet<_>>();
+                let (region_width, region_height) = if symmetry.is_square() {
+                    (width.min(height), width.min(height))
+                } else {
+                    (width, height)
+                };
+                let (top, left) = ((height - region_height) / 2, (width - region_width) / 2);
+                for (line, column) in &cells {
+                    let images =
+                        symmetry.images(line - top, column - left, region_width, region_height);
+                    for (image_line, image_column) in images {
+                        assert!(
+                            cells.contains(&(image_line + top, image_column + left)),
+                            "{} soup of {}x{}",
+                            symmetry,
+                            width,
+                            height
+                        );
+                    }
+                }
+            }
+        }
+        assert!("D3".parse::<Symmetry>().is_err());
+        assert_eq!(Symmetry::D8, "d8".parse().unwrap());
+    }
+}
@@ -0,0 +1,264 @@
+use crate::universe::neighborhood::Neighborhood;
+use crate::universe::ParseError;
+use rand::Rng;
+use std::fmt::{Display, Formatter, Result};
+
+/// Probabilities are stored in millionths, so rules can be compared and written back exactly.
+const CERTAIN: u32 = 1_000_000;
+
+/// Life-like rule where each neighbor count gives the probability of a birth or a survival,
+/// written after the count in parentheses, e.g. `B3(0.9)/S2(0.95)3` for a noisy Life.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Stochastic {
+    birth: [u32; 9],
+    survival: [u32; 9],
+    neighborhood: Neighborhood,
+}
+
+fn parse_probability(value: &str, rule: &str) -> std::result::Result<u32, ParseError> {
+    let invalid = || {
+        ParseError::new(format!(
+            "invalid probability '{}' in rule '{}'",
+            value, rule
+        ))
+    };
+    let (units, decimals) = value.split_once('.').unwrap_or((value, ""));
+    let digits = |part: &str| part.chars().all(|digit| digit.is_ascii_digit());
+    let length = units.len() + decimals.len();
+    if length == 0 || units.len() > 1 || decimals.len() > 6 || !digits(units) || !digits(decimals) {
+        return Err(invalid());
+    }
+    let chance = format!("{}{:0<6}", units, decimals)
+        .parse::<u32>()
+        .map_err(|_| invalid())?;
+    if chance > CERTAIN {
+        return Err(invalid());
+    }
+    Ok(chance)
+}
+
+fn parse_chances(value: &str, rule: &str) -> std::result::Result<[u32; 9], ParseError> {
+    let mut chances = [0; 9];
+    let mut entries = value;
+    while let Some(entry) = entries.chars().next() {
+        let count = match entry.to_digit(10) {
+            Some(digit) if digit < 9 => digit as usize,
+            _ => {
+                return Err(ParseError::new(format!(
+                    "invalid neighbor count '{}' in rule '{}'",
+                    entry, rule
+                )))
+            }
+        };
+        entries = &entries[1..];
+        chances[count] = match entries.strip_prefix('(') {
+            Some(rest) => {
+                let (probability, rest) = rest
+                    .split_once(')')
+                    .ok_or_else(|| ParseError::new(format!("missing ')' in rule '{}'", rule)))?;
+                entries = rest;
+                parse_probability(probability, rule)?
+            }
+            None => CERTAIN,
+        };
+    }
+    Ok(chances)
+}
+
+fn format_chances(chances: &[u32; 9]) -> String {
+    let mut result = String::new();
+    for (count, chance) in chances.iter().enumerate() {
+        match *chance {
+            0 => {}
+            CERTAIN => result.push_str(&count.to_string()),
+            chance => {
+                let decimals = format!("{:06}", chance);
+                result.push_str(&format!("{}(0.{})", count, decimals.trim_end_matches('0')));
+            }
+        }
+    }
+    result
+}
+
+fn happens(chance: u32, random: &mut impl Rng) -> bool {
+    match chance {
+        0 => false,
+        CERTAIN => true,
+        chance => random.gen_range(0..CERTAIN) < chance,
+    }
+}
+
+impl Stochastic {
+    /// Whether the rule counts have probabilities.
+    pub fn matches(value: &str) -> bool {
+        value.contains('(')
+    }
+
+    pub fn parse(value: &str) -> std::result::Result<Self, ParseError> {
+        let invalid = || ParseError::new(format!("invalid rule '{}'", value));
+        let (counts, neighborhood) = Neighborhood::split(value)?;
+        let (mut birth, mut survival) = (None, None);
+        for part in counts.split('/') {
+            let mut chars = part.chars();
+            let prefix = chars.next();
+            let chances = chars.as_str();
+            match prefix {
+                Some('B' | 'b') if birth.is_none() => birth = Some(parse_chances(chances, value)?),
+                Some('S' | 's') if survival.is_none() => {
+                    survival = Some(parse_chances(chances, value)?)
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        let (birth, survival) = birth.zip(survival).ok_or_else(invalid)?;
+        let size = neighborhood.size();
+        if (size + 1..9).any(|count| birth[count] > 0 || survival[count] > 0) {
+            return Err(ParseError::new(format!(
+                "neighbor counts above {} are not possible in rule '{}'",
+                size, value
+            )));
+        }
+        Ok(Stochastic {
+            birth,
+            survival,
+            neighborhood,
+        })
+    }
+
+    pub fn neighborhood(&self) -> &Neighborhood {
+        &self.neighborhood
+    }
+
+    /// Whether every probability is 0 or 1, making this an ordinary life-like rule.
+    pub fn is_certain(&self) -> bool {
+        self.birth
+            .iter()
+            .chain(self.survival.iter())
+            .all(|chance| *chance == 0 || *chance == CERTAIN)
+    }
+
+    fn chance(&self, alive: bool, count: u32) -> u32 {
+        if alive {
+            self.survival[count as usize]
+        } else {
+            self.birth[count as usize]
+        }
+    }
+
+    /// Whether a cell with the number of live neighbors is alive in the next generation.
+    pub fn next(&self, alive: bool, count: u32, random: &mut impl Rng) -> bool {
+        happens(self.chance(alive, count), random)
+    }
+
+    /// Whether a cell with the number of live neighbors can be alive in the next generation.
+    pub fn possible(&self, alive: bool, count: u32) -> bool {
+        self.chance(alive, count) > 0
+    }
+
+    /// The most likely outcome of [`next`](Stochastic::next).
+    pub fn likely(&self, alive: bool, count: u32) -> bool {
+        self.chance(alive, count) >= CERTAIN / 2
+    }
+}
+
+impl Display for Stochastic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "B{}/S{}{}",
+            format_chances(&self.birth),
+            format_chances(&self.survival),
+            self.neighborhood
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{EngineKind, LifeEngine};
+    use crate::universe::rule::Rule;
+    use crate::universe::Universe;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_parse() {
+        let rule = Stochastic::parse("B3(0.9)/S2(0.95)3").unwrap();
+        assert_eq!("B3(0.9)/S2(0.95)3", rule.to_string());
+        assert_eq!(
+            "B34(0.000001)/S23(0.5)V",
+            Stochastic::parse("b34(.000001)/s23(0.50)v")
+                .unwrap()
+                .to_string()
+        );
+        assert!(Stochastic::parse("B3(1)/S2(0)3").unwrap().is_certain());
+        assert!(!rule.is_certain());
+        assert!(rule.likely(false, 3));
+        assert!(!rule.likely(true, 1));
+        for invalid in [
+            "B3(1.1)/S23",
+            "B3(0.1/S23",
+            "B3(0.0000001)/S23",
+            "B3(-0.5)/S23",
+            "B3(x)/S23",
+            "B3()/S23",
+            "B3(.)/S23",
+            "B9(0.5)/S23",
+            "B3(0.5)",
+            "B3(0.5)/S23/S2",
+            "B5(0.5)/S23V",
+            "B3(0.5)/Ñ",
+            "Ñ(0.5)",
+        ] {
+            assert!(Stochastic::parse(invalid).is_err(), "rule {}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_rule() {
+        assert_eq!(
+            "B3(0.25)/S23",
+            Rule::parse("B3(.25)/S23").unwrap().to_string()
+        );
+        // certain probabilities give an ordinary rule
+        assert_eq!(Rule::conway(), Rule::parse("B3(1)/S2(1.0)3").unwrap());
+        assert!(Rule::parse("B0(0.01)3/S23").is_err());
+        assert!(Rule::parse("B0(0.01)3/S23:T20,20").is_ok());
+        let rule = Rule::parse("B3(0.3)/S23").unwrap();
+        assert!(!EngineKind::HashLife.supports(&rule));
+        assert!(EngineKind::Universe.supports(&rule));
+        let mut random = StdRng::seed_from_u64(1);
+        let births = (0..10000)
+            .filter(|_| rule.next_state_with(0, 0xe0, &mut random) == 1)
+            .count();
+        assert!((2800..3200).contains(&births), "{} births", births);
+        assert_eq!(1, rule.next_state_with(1, 0xc0, &mut random));
+        assert_eq!(0, rule.next_state_with(1, 0x80, &mut random));
+    }
+
+    #[test]
+    fn test_seeded() {
+        let soup = |seed: u64| {
+            let mut universe = Universe::new("11011\n10110\n01101\n11010\n00111");
+            universe.rule = Rule::parse("B3(0.8)/S2(0.9)3").unwrap();
+            universe.seed(seed);
+            universe
+        };
+        let mut first = soup(7);
+        let mut second = soup(7);
+        let mut third = soup(7);
+        third.seed(8);
+        first.step(20);
+        second.step(10);
+        // the generator is part of the universe, and goes on with the copies
+        let mut copy = second.clone();
+        second.step(10);
+        copy.step(10);
+        third.step(20);
+        assert!(first.population() > 0);
+        assert_eq!(first, second);
+        assert_eq!(first, copy);
+        assert_ne!(first, third);
+    }
+}
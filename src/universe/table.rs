@@ -0,0 +1,610 @@
+use crate::universe::neighborhood::Neighborhood;
+use crate::universe::ParseError;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+
+/// Golly's WireWorld rule, always available by name.
+pub const WIREWORLD: &str = r#"@RULE WireWorld
+
+Electrons (1) travel along copper wires (3), leaving a tail (2) behind them.
+
+@TABLE
+n_states:4
+neighborhood:Moore
+symmetries:permute
+
+var a={0,1,2,3}
+var b={a}
+var c={a}
+var d={a}
+var e={a}
+var f={a}
+var g={a}
+var h={a}
+var o={0,2,3}
+var p={o}
+var q={o}
+var r={o}
+var s={o}
+var t={o}
+var u={o}
+
+# C,N,NE,E,SE,S,SW,W,NW,C'
+1,a,b,c,d,e,f,g,h,2
+2,a,b,c,d,e,f,g,h,3
+# copper becomes an electron head with one or two heads around
+3,1,o,p,q,r,s,t,u,1
+3,1,1,o,p,q,r,s,t,1
+
+@COLORS
+0 48 48 48
+1 0 128 255
+2 255 255 255
+3 255 128 0
+"#;
+
+// neighbors in the order of the table columns, clockwise from the top
+const MOORE: [(i32, i32); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+const VON_NEUMANN: [(i32, i32); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+const HEXAGONAL: [(i32, i32); 6] = [(-1, 0), (0, 1), (1, 1), (1, 0), (0, -1), (-1, -1)];
+
+thread_local! {
+    static TABLES: RefCell<BTreeMap<String, Rc<RuleTable>>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+struct States([u64; 4]);
+
+impl States {
+    fn insert(&mut self, state: u8) {
+        self.0[state as usize / 64] |= 1 << (state % 64);
+    }
+
+    fn contains(&self, state: u8) -> bool {
+        self.0[state as usize / 64] & (1 << (state % 64)) != 0
+    }
+
+    fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..=255).filter(move |state| self.contains(*state))
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+enum Symmetry {
+    /// Rotations by a multiple of `step` neighbors, optionally with their reflections.
+    Rotations { step: usize, reflect: bool },
+    /// Any order of the neighbors.
+    Permute,
+}
+
+#[derive(Eq, PartialEq, Debug, Clone)]
+struct Transition {
+    state: u8,
+    neighbors: Vec<States>,
+    next: u8,
+}
+
+/// A Golly rule table (the `@RULE`, `@TABLE` and `@COLORS` sections of a `.rule` file).
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct RuleTable {
+    name: String,
+    states: u16,
+    neighborhood: Neighborhood,
+    symmetry: Symmetry,
+    transitions: Vec<Transition>,
+    colors: BTreeMap<u8, (u8, u8, u8)>,
+}
+
+fn parse_symmetry(value: &str, neighbors: usize) -> std::result::Result<Symmetry, ParseError> {
+    let invalid = || ParseError::new(format!("unsupported symmetries '{}'", value));
+    let (rotations, reflect) = match value {
+        "none" => (1, false),
+        "reflect_horizontal" => (1, true),
+        "permute" => return Ok(Symmetry::Permute),
+        _ => {
+            let rotations = value.strip_prefix("rotate").ok_or_else(invalid)?;
+            let (rotations, reflect) = match rotations.strip_suffix("reflect") {
+                Some(rotations) => (rotations, true),
+                None => (rotations, false),
+            };
+            (rotations.parse::<usize>().map_err(|_| invalid())?, reflect)
+        }
+    };
+    if rotations == 0 || !neighbors.is_multiple_of(rotations) {
+        return Err(invalid());
+    }
+    Ok(Symmetry::Rotations {
+        step: neighbors / rotations,
+        reflect,
+    })
+}
+
+impl RuleTable {
+    pub fn parse(value: &str) -> std::result::Result<Self, ParseError> {
+        let mut name = None;
+        let mut section = "";
+        let mut states = None;
+        let mut neighborhood = None;
+        let mut symmetry = None;
+        let mut variables: BTreeMap<String, States> = BTreeMap::new();
+        let mut transitions = vec![];
+        let mut colors = BTreeMap::new();
+        let mut gradient = None;
+        for line in value.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if let Some(header) = line.strip_prefix('@') {
+                let (kind, rest) = header.split_once(' ').unwrap_or((header, ""));
+                section = kind;
+                if kind == "RULE" {
+                    name = Some(rest.trim().to_string());
+                }
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            match section {
+                "TABLE" => {
+                    if let Some((key, value)) = line.split_once(':') {
+                        let value = value.trim();
+                        match key.trim() {
+                            "n_states" => {
+                                // as in Golly, the states are numbered 0 to 255
+                                states = match value.parse::<u16>() {
+                                    Ok(states) if (2..=256).contains(&states) => Some(states),
+                                    _ => {
+                                        return Err(ParseError::new(format!(
+                                            "invalid number of states '{}'",
+                                            value
+                                        )))
+                                    }
+                                }
+                            }
+                            "neighborhood" => {
+                                neighborhood = Some(match value {
+                                    "Moore" => Neighborhood::Moore,
+                                    "vonNeumann" => Neighborhood::VonNeumann,
+                                    "hexagonal" => Neighborhood::Hexagonal,
+                                    _ => {
+                                        return Err(ParseError::new(format!(
+                                            "unsupported neighborhood '{}'",
+                                            value
+                                        )))
+                                    }
+                                })
+                            }
+                            "symmetries" => symmetry = Some(value.to_string()),
+                            _ => {
+                                return Err(ParseError::new(format!(
+                                    "unexpected rule table line '{}'",
+                                    line
+                                )))
+                            }
+                        }
+                    } else if let Some(variable) = line.strip_prefix("var ") {
+                        let (key, values) = variable.split_once('=').ok_or_else(|| {
+                            ParseError::new(format!("invalid variable '{}'", line))
+                        })?;
+                        let values = parse_states(values, &variables, states)?;
+                        variables.insert(key.trim().to_string(), values);
+                    } else {
+                        let neighborhood = neighborhood.unwrap_or(Neighborhood::Moore);
+                        let states = states.ok_or_else(|| {
+                            ParseError::new("missing n_states in rule table".to_string())
+                        })?;
+                        transitions.extend(parse_transition(
+                            line,
+                            &variables,
+                            offsets(&neighborhood).len(),
+                            states,
+                        )?);
+                    }
+                }
+                "COLORS" => {
+                    let values = line
+                        .split_whitespace()
+                        .map(|value| value.parse::<u8>())
+                        .collect::<std::result::Result<Vec<_>, _>>()
+                        .map_err(|_| ParseError::new(format!("invalid color '{}'", line)))?;
+                    match values[..] {
+                        [state, red, green, blue] => {
+                            colors.insert(state, (red, green, blue));
+                        }
+                        [red, green, blue, last_red, last_green, last_blue] => {
+                            gradient = Some(((red, green, blue), (last_red, last_green, last_blue)))
+                        }
+                        _ => return Err(ParseError::new(format!("invalid color '{}'", line))),
+                    }
+                }
+                _ => {}
+            }
+        }
+        let name = name
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| ParseError::new("missing @RULE name".to_string()))?;
+        let states = states
+            .ok_or_else(|| ParseError::new(format!("missing @TABLE section in rule '{}'", name)))?;
+        // as in Golly, the gradient goes from the first live state to the last one, and the
+        // colors of single states take precedence
+        if let Some((first, last)) = gradient {
+            let steps = i32::from(states).max(3) - 2;
+            let mix = |first: u8, last: u8, step: i32| {
+                let value = i32::from(first) + (i32::from(last) - i32::from(first)) * step / steps;
+                value as u8
+            };
+            for state in 1..states {
+                let step = i32::from(state) - 1;
+                colors.entry(state as u8).or_insert((
+                    mix(first.0, last.0, step),
+                    mix(first.1, last.1, step),
+                    mix(first.2, last.2, step),
+                ));
+            }
+        }
+        let neighborhood = neighborhood.unwrap_or(Neighborhood::Moore);
+        let symmetry = parse_symmetry(
+            symmetry.as_deref().unwrap_or("none"),
+            offsets(&neighborhood).len(),
+        )?;
+        Ok(RuleTable {
+            name,
+            states,
+            neighborhood,
+            symmetry,
+            transitions,
+            colors,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn states(&self) -> u16 {
+        self.states
+    }
+
+    pub fn neighborhood(&self) -> &Neighborhood {
+        &self.neighborhood
+    }
+
+    /// Positions of the neighbors, in the order expected by [`RuleTable::next_state`].
+    pub fn offsets(&self) -> &'static [(i32, i32)] {
+        offsets(&self.neighborhood)
+    }
+
+    /// Display color of the state, from the `@COLORS` section.
+    pub fn color(&self, state: u8) -> Option<(u8, u8, u8)> {
+        self.colors.get(&state).copied()
+    }
+
+    /// The next state of a cell, given the states of its neighbors.
+    pub fn next_state(&self, state: u8, neighbors: &[u8]) -> u8 {
+        self.transitions
+            .iter()
+            .find(|transition| transition.state == state && self.matches(transition, neighbors))
+            .map_or(state, |transition| transition.next)
+    }
+
+    fn matches(&self, transition: &Transition, neighbors: &[u8]) -> bool {
+        let count = neighbors.len();
+        match self.symmetry {
+            Symmetry::Permute => assign(&transition.neighbors, neighbors, &mut vec![false; count]),
+            Symmetry::Rotations { step, reflect } => (0..count).step_by(step).any(|rotation| {
+                let reflections: &[bool] = if reflect { &[false, true] } else { &[false] };
+                reflections.iter().any(|reflected| {
+                    transition
+                        .neighbors
+                        .iter()
+                        .enumerate()
+                        .all(|(index, states)| {
+                            let index = if *reflected { count - index } else { index };
+                            states.contains(neighbors[(index + rotation) % count])
+                        })
+                })
+            }),
+        }
+    }
+
+    /// Registers the table, so rules and patterns can refer to it by name.
+    pub fn register(self) -> Rc<RuleTable> {
+        let table = Rc::new(self);
+        TABLES.with(|tables| {
+            tables
+                .borrow_mut()
+                .insert(table.name.to_lowercase(), table.clone())
+        });
+        table
+    }
+
+    /// A registered table, or one of the built-in tables.
+    pub fn find(name: &str) -> Option<Rc<RuleTable>> {
+        let registered = TABLES.with(|tables| tables.borrow().get(&name.to_lowercase()).cloned());
+        registered.or_else(|| {
+            if name.eq_ignore_ascii_case("WireWorld") {
+                RuleTable::parse(WIREWORLD).ok().map(RuleTable::register)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+fn offsets(neighborhood: &Neighborhood) -> &'static [(i32, i32)] {
+    match neighborhood {
+        Neighborhood::VonNeumann => &VON_NEUMANN,
+        Neighborhood::Hexagonal => &HEXAGONAL,
+        _ => &MOORE,
+    }
+}
+
+fn assign(entries: &[States], neighbors: &[u8], used: &mut [bool]) -> bool {
+    let (first, rest) = match entries.split_first() {
+        Some(split) => split,
+        None => return true,
+    };
+    for (index, state) in neighbors.iter().enumerate() {
+        if !used[index] && first.contains(*state) {
+            used[index] = true;
+            if assign(rest, neighbors, used) {
+                used[index] = false;
+                return true;
+            }
+            used[index] = false;
+        }
+    }
+    false
+}
+
+fn parse_state(value: &str, states: Option<u16>) -> Option<u8> {
+    let state = value.parse::<u8>().ok()?;
+    match states {
+        Some(states) if u16::from(state) >= states => None,
+        _ => Some(state),
+    }
+}
+
+fn parse_states(
+    value: &str,
+    variables: &BTreeMap<String, States>,
+    states: Option<u16>,
+) -> std::result::Result<States, ParseError> {
+    let mut result = States::default();
+    let value = value.trim();
+    let entries = value
+        .strip_prefix('{')
+        .and_then(|value| value.strip_suffix('}'))
+        .unwrap_or(value);
+    for entry in entries.split(',').map(|entry| entry.trim()) {
+        if let Some(state) = parse_state(entry, states) {
+            result.insert(state);
+        } else if let Some(variable) = variables.get(entry) {
+            variable.iter().for_each(|state| result.insert(state));
+        } else {
+            return Err(ParseError::new(format!(
+                "invalid state or variable '{}' in rule table",
+                entry
+            )));
+        }
+    }
+    Ok(result)
+}
+
+fn parse_transition(
+    line: &str,
+    variables: &BTreeMap<String, States>,
+    neighbors: usize,
+    states: u16,
+) -> std::result::Result<Vec<Transition>, ParseError> {
+    let mut entries = line
+        .split(|entry: char| entry == ',' || entry.is_whitespace())
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| entry.to_string())
+        .collect::<Vec<_>>();
+    // states and variables may be written without separators when they are one character long
+    if entries.len() == 1 {
+        entries = line.chars().map(|entry| entry.to_string()).collect();
+    }
+    if entries.len() != neighbors + 2 {
+        return Err(ParseError::new(format!(
+            "expected {} entries in transition '{}'",
+            neighbors + 2,
+            line
+        )));
+    }
+    let bound = entries
+        .iter()
+        .filter(|entry| variables.contains_key(*entry))
+        .filter(|entry| entries.iter().filter(|other| other == entry).count() > 1)
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    let mut bindings = vec![BTreeMap::new()];
+    for variable in &bound {
+        bindings = bindings
+            .into_iter()
+            .flat_map(|binding: BTreeMap<&str, u8>| {
+                variables[variable].iter().map(move |state| {
+                    let mut binding = binding.clone();
+                    binding.insert(variable.as_str(), state);
+                    binding
+                })
+            })
+            .collect();
+    }
+    let mut transitions = vec![];
+    for binding in bindings {
+        let mut sets = vec![];
+        for entry in &entries {
+            let set = match binding.get(entry.as_str()) {
+                Some(state) => {
+                    let mut set = States::default();
+                    set.insert(*state);
+                    set
+                }
+                None => parse_states(entry, variables, Some(states))?,
+            };
+            sets.push(set);
+        }
+        let next = sets.pop().and_then(|set| {
+            let mut values = set.iter();
+            match (values.next(), values.next()) {
+                (Some(next), None) => Some(next),
+                _ => None,
+            }
+        });
+        let next = next.ok_or_else(|| {
+            ParseError::new(format!(
+                "the next state must be a state or a bound variable in '{}'",
+                line
+            ))
+        })?;
+        let state = sets.remove(0);
+        for current in state.iter() {
+            transitions.push(Transition {
+                state: current,
+                neighbors: sets.clone(),
+                next,
+            });
+        }
+    }
+    Ok(transitions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::LifeEngine;
+    use crate::universe::rule::Rule;
+    use crate::universe::Universe;
+
+    fn table(transitions: &str) -> RuleTable {
+        RuleTable::parse(&format!("@RULE Test\n@TABLE\nn_states:3\n{}", transitions)).unwrap()
+    }
+
+    #[test]
+    fn test_wireworld() {
+        let mut universe = Universe::from_rle("x = 7, y = 1, rule = WireWorld\nBA5C!").unwrap();
+        assert_eq!(4, universe.rule.states());
+        universe.step(1);
+        assert_eq!(
+            "x = 7, y = 1, rule = WireWorld\nCBA4C!\n",
+            universe.to_rle()
+        );
+        // an electron goes around a loop with diagonal corners in 10 generations
+        let mut universe =
+            Universe::from_rle("x = 6, y = 3, rule = WireWorld\n.BA2C$C4.C$.4C!").unwrap();
+        let initial = universe.clone();
+        universe.step(5);
+        assert_ne!(initial, universe);
+        universe.step(5);
+        assert_eq!(initial, universe);
+        let wireworld = RuleTable::find("wireworld").unwrap();
+        assert_eq!(1, wireworld.next_state(3, &[0, 1, 0, 0, 0, 1, 0, 0]));
+        assert_eq!(3, wireworld.next_state(3, &[1, 1, 1, 0, 0, 0, 0, 0]));
+        assert_eq!(
+            Some((0, 128, 255)),
+            Rule::parse("WireWorld").unwrap().color(1)
+        );
+        assert_eq!(None, Rule::parse("WireWorld").unwrap().color(4));
+    }
+
+    #[test]
+    fn test_symmetries() {
+        let none = table("neighborhood:vonNeumann\n0,1,0,0,0,1");
+        assert_eq!(1, none.next_state(0, &[1, 0, 0, 0]));
+        assert_eq!(0, none.next_state(0, &[0, 1, 0, 0]));
+        let rotate4 = table("neighborhood:vonNeumann\nsymmetries:rotate4\n0,1,0,0,0,1");
+        assert_eq!(1, rotate4.next_state(0, &[0, 1, 0, 0]));
+        assert_eq!(1, rotate4.next_state(0, &[0, 0, 0, 1]));
+        let reflect = table("symmetries:reflect_horizontal\n0,0,1,0,0,0,0,0,0,1");
+        assert_eq!(1, reflect.next_state(0, &[0, 0, 0, 0, 0, 0, 0, 1]));
+        assert_eq!(0, reflect.next_state(0, &[0, 0, 0, 1, 0, 0, 0, 0]));
+        let rotate8 = table("symmetries:rotate8reflect\n0,1,2,0,0,0,0,0,0,1");
+        assert_eq!(1, rotate8.next_state(0, &[0, 0, 0, 2, 1, 0, 0, 0]));
+        assert_eq!(0, rotate8.next_state(0, &[0, 0, 0, 1, 0, 2, 0, 0]));
+        let permute = table("neighborhood:hexagonal\nsymmetries:permute\n0,1,2,0,0,0,0,1");
+        assert_eq!(1, permute.next_state(0, &[0, 0, 2, 0, 1, 0]));
+        assert_eq!(0, permute.next_state(0, &[0, 0, 2, 0, 2, 0]));
+    }
+
+    #[test]
+    fn test_variables() {
+        let bound = table("neighborhood:vonNeumann\nvar a={1,2}\nvar b={a}\n0,a,a,b,0,a");
+        assert_eq!(2, bound.next_state(0, &[2, 2, 1, 0]));
+        assert_eq!(1, bound.next_state(0, &[1, 1, 2, 0]));
+        assert_eq!(0, bound.next_state(0, &[1, 2, 2, 0]));
+        // transitions are tried in order, unmatched cells keep their state
+        let compact = table("neighborhood:vonNeumann\nvar a={1,2}\n011112\n0a0a01");
+        assert_eq!(2, compact.next_state(0, &[1, 1, 1, 1]));
+        assert_eq!(2, compact.next_state(2, &[1, 1, 1, 1]));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |transitions: &str| {
+            RuleTable::parse(&format!("@RULE Test\n@TABLE\nn_states:3\n{}", transitions))
+        };
+        assert!(parse("0,1,0,0,0,0,0,0,0").is_err());
+        assert!(parse("0,3,0,0,0,0,0,0,0,1").is_err());
+        assert!(parse("0,x,0,0,0,0,0,0,0,1").is_err());
+        assert!(parse("var a={1,2}\n0,a,0,0,0,0,0,0,0,a").is_ok());
+        assert!(parse("var a={1,2}\n0,a,0,0,0,0,0,0,0,b").is_err());
+        assert!(parse("var a={1,2}\nvar b={a}\n0,a,0,0,0,0,0,0,0,b").is_err());
+        assert!(parse("symmetries:rotate3\n0,1,0,0,0,0,0,0,0,1").is_err());
+        assert!(parse("neighborhood:oneDimensional").is_err());
+        assert!(RuleTable::parse("@RULE\n@TABLE\nn_states:2").is_err());
+        assert!(RuleTable::parse("@RULE Test\n@TREE").is_err());
+        assert!(RuleTable::parse("@RULE Test\n@TABLE\nn_states:1").is_err());
+        assert!(RuleTable::parse("@RULE Test\n@TABLE\nn_states:257").is_err());
+        assert!(parse("@COLORS\n1 255 0").is_err());
+        assert!(parse("@COLORS\n1 255 0 0 0").is_err());
+        assert!(Rule::parse("NoSuchRule").is_err());
+    }
+
+    #[test]
+    fn test_colors() {
+        let table = RuleTable::parse(
+            "@RULE Test\n@TABLE\nn_states:256\n0,255,0,0,0,0,0,0,0,1\n@COLORS\n0 48 48 48\n255 0 0 0 255 0\n2 1 2 3",
+        )
+        .unwrap();
+        assert_eq!(256, table.states());
+        assert_eq!(1, table.next_state(0, &[255, 0, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(Some((48, 48, 48)), table.color(0));
+        // the gradient goes from state 1 to state 255, single colors override it
+        assert_eq!(Some((255, 0, 0)), table.color(1));
+        assert_eq!(Some((1, 2, 3)), table.color(2));
+        assert_eq!(Some((128, 127, 0)), table.color(128));
+        assert_eq!(Some((0, 255, 0)), table.color(255));
+    }
+
+    #[test]
+    fn test_register() {
+        RuleTable::parse(
+            "@RULE Spread\n@TABLE\nn_states:2\nsymmetries:permute\n0,1,0,0,0,0,0,0,0,1",
+        )
+        .unwrap()
+        .register();
+        let rule = Rule::parse("Spread:T10,10").unwrap();
+        assert_eq!("Spread:T10,10", rule.to_string());
+        let mut universe = Universe::new("1");
+        universe.rule = rule;
+        universe.step(1);
+        assert_eq!(9, universe.population());
+        // births from empty neighborhoods are only possible on finite grids
+        RuleTable::parse("@RULE Fill\n@TABLE\nn_states:2\n0,0,0,0,0,0,0,0,0,1")
+            .unwrap()
+            .register();
+        assert!(Rule::parse("Fill").is_err());
+        assert!(Rule::parse("Fill:P5,5").is_ok());
+    }
+}
@@ -0,0 +1,196 @@
+use crate::universe::ParseError;
+use std::fmt::{Display, Formatter, Result};
+
+/// Finite grids are scanned entirely at each generation, which freezes the page beyond this.
+const MAX_AREA: i64 = 1 << 20;
+
+/// The shape of the grid, written as a Golly-style rule suffix such as `:T80,60`.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub enum Topology {
+    /// Unbounded plane.
+    Plane,
+    /// Finite rectangle surrounded by dead cells (`:P`).
+    Bounded { width: i32, height: i32 },
+    /// Opposite edges are joined (`:T`).
+    Torus { width: i32, height: i32 },
+    /// One pair of edges is joined with a twist (`:K`): the top and bottom edges when
+    /// `twisted_width` is set (`:K80*,60`), otherwise the left and right edges (`:K80,60*`).
+    KleinBottle {
+        width: i32,
+        height: i32,
+        twisted_width: bool,
+    },
+    /// Both pairs of edges are joined with a twist (`:C`).
+    CrossSurface { width: i32, height: i32 },
+}
+
+fn parse_size(value: &str, suffix: &str) -> std::result::Result<i32, ParseError> {
+    match value.parse::<i32>() {
+        Ok(size) if size > 0 => Ok(size),
+        _ => Err(ParseError::new(format!(
+            "invalid grid size '{}' in topology ':{}'",
+            value, suffix
+        ))),
+    }
+}
+
+impl Topology {
+    /// Parses the part of a rule after the `:`, e.g. `T80,60`.
+    pub fn parse(suffix: &str) -> std::result::Result<Self, ParseError> {
+        let suffix = suffix.trim();
+        let invalid = || ParseError::new(format!("invalid topology ':{}'", suffix));
+        let kind = suffix.chars().next().ok_or_else(invalid)?;
+        let (width, height) = suffix[kind.len_utf8()..]
+            .split_once(',')
+            .ok_or_else(invalid)?;
+        let twisted_width = width.ends_with('*');
+        let twisted_height = height.ends_with('*');
+        let width = parse_size(width.trim_end_matches('*'), suffix)?;
+        let height = parse_size(height.trim_end_matches('*'), suffix)?;
+        if i64::from(width) * i64::from(height) > MAX_AREA {
+            return Err(ParseError::new(format!(
+                "grid ':{}' has more than {} cells",
+                suffix, MAX_AREA
+            )));
+        }
+        let topology = match (kind.to_ascii_uppercase(), twisted_width, twisted_height) {
+            ('P', false, false) => Topology::Bounded { width, height },
+            ('T', false, false) => Topology::Torus { width, height },
+            ('K', true, false) | ('K', false, true) => Topology::KleinBottle {
+                width,
+                height,
+                twisted_width,
+            },
+            ('C', false, false) => Topology::CrossSurface { width, height },
+            _ => return Err(invalid()),
+        };
+        Ok(topology)
+    }
+
+    /// Width and height of finite grids.
+    pub fn size(&self) -> Option<(i32, i32)> {
+        match *self {
+            Topology::Plane => None,
+            Topology::Bounded { width, height }
+            | Topology::Torus { width, height }
+            | Topology::KleinBottle { width, height, .. }
+            | Topology::CrossSurface { width, height } => Some((width, height)),
+        }
+    }
+
+    /// Maps a position to the cell it refers to, or `None` if it is outside a bounded grid.
+    pub fn wrap(&self, line: i32, column: i32) -> Option<(i32, i32)> {
+        let (width, height) = match self.size() {
+            Some(size) => size,
+            None => return Some((line, column)),
+        };
+        let line_outside = !(0..height).contains(&line);
+        let column_outside = !(0..width).contains(&column);
+        let (twist_lines, twist_columns) = match *self {
+            Topology::Bounded { .. } => {
+                return if line_outside || column_outside {
+                    None
+                } else {
+                    Some((line, column))
+                }
+            }
+            Topology::KleinBottle { twisted_width, .. } => (twisted_width, !twisted_width),
+            Topology::CrossSurface { .. } => (true, true),
+            _ => (false, false),
+        };
+        let (mut line, mut column) = (line, column);
+        // crossing a twisted edge mirrors the position along that edge
+        if line_outside && twist_lines {
+            column = width - 1 - column;
+        }
+        if column_outside && twist_columns {
+            line = height - 1 - line;
+        }
+        Some((line.rem_euclid(height), column.rem_euclid(width)))
+    }
+}
+
+impl Display for Topology {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match *self {
+            Topology::Plane => Ok(()),
+            Topology::Bounded { width, height } => write!(f, ":P{},{}", width, height),
+            Topology::Torus { width, height } => write!(f, ":T{},{}", width, height),
+            Topology::KleinBottle {
+                width,
+                height,
+                twisted_width,
+            } => {
+                if twisted_width {
+                    write!(f, ":K{}*,{}", width, height)
+                } else {
+                    write!(f, ":K{},{}*", width, height)
+                }
+            }
+            Topology::CrossSurface { width, height } => write!(f, ":C{},{}", width, height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        for value in ["P80,60", "T80,60", "K80*,60", "K80,60*", "C80,60"] {
+            assert_eq!(
+                format!(":{}", value),
+                Topology::parse(value).unwrap().to_string()
+            );
+        }
+        assert_eq!(
+            Topology::Torus {
+                width: 80,
+                height: 60
+            },
+            Topology::parse("t80,60").unwrap()
+        );
+        assert!(Topology::parse("T80").is_err());
+        assert!(Topology::parse("T0,60").is_err());
+        assert!(Topology::parse("K80,60").is_err());
+        assert!(Topology::parse("X80,60").is_err());
+        assert!(Topology::parse("").is_err());
+        assert!(Topology::parse("Ñ80,60").is_err());
+        assert!(Topology::parse("T1024,1024").is_ok());
+        assert!(Topology::parse("T100000,100000").is_err());
+        assert!(Topology::parse("P2000000000,2").is_err());
+    }
+
+    #[test]
+    fn test_wrap() {
+        let size = (10, 5);
+        let bounded = Topology::Bounded {
+            width: size.0,
+            height: size.1,
+        };
+        assert_eq!(Some((0, 0)), bounded.wrap(0, 0));
+        assert_eq!(None, bounded.wrap(-1, 0));
+        assert_eq!(None, bounded.wrap(0, 10));
+        let torus = Topology::Torus {
+            width: size.0,
+            height: size.1,
+        };
+        assert_eq!(Some((4, 9)), torus.wrap(-1, -1));
+        assert_eq!(Some((0, 3)), torus.wrap(5, 3));
+        let klein = Topology::KleinBottle {
+            width: size.0,
+            height: size.1,
+            twisted_width: true,
+        };
+        assert_eq!(Some((0, 6)), klein.wrap(5, 3));
+        assert_eq!(Some((1, 0)), klein.wrap(1, 10));
+        let cross = Topology::CrossSurface {
+            width: size.0,
+            height: size.1,
+        };
+        assert_eq!(Some((0, 6)), cross.wrap(5, 3));
+        assert_eq!(Some((3, 0)), cross.wrap(1, 10));
+        assert_eq!(Some((7, 3)), Topology::Plane.wrap(7, 3));
+    }
+}